@@ -1,6 +1,11 @@
 extern crate bindgen;
 
 fn main() {
+    // Nothing to link without the `libmpv` feature.
+    if std::env::var_os("CARGO_FEATURE_LIBMPV").is_none() {
+        return;
+    }
+
     // Use pkg-config to find libmpv
     let libmpv = pkg_config::Config::new()
         .probe("mpv")
@@ -1,36 +1,25 @@
-use std::{
-    ffi::{CStr, CString},
-    os::raw::c_void,
-    ptr::null,
-};
-
-use libc::{c_char, c_int};
 use libmpv_rs::safe::*;
 
-fn check(error: Option<MpvError>) {
-    if let Some(error) = error {
-        panic!("mpv API error: {}", error.get_error_string());
-    }
-}
-
 fn main() {
-    let Some(mut ctx) = MpvHandle::new() else {
-        panic!("Cannot initialize mpv handle");
+    let mut ctx = match MpvBuilder::new()
+        .input_default_bindings(true)
+        .input_vo_keyboard(true)
+        .osc(true)
+        .build()
+    {
+        Ok(ctx) => ctx,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("mpv API error: {}", error);
+            }
+            std::process::exit(1);
+        }
     };
-    println!("Handle inited");
-    check(ctx.set_option("input-default-bindings".into(), Node::String("yes".into())));
-    println!("keyboard");
-    ctx.set_option("input-vo-keyboard".into(), Node::String("yes".into()));
-    println!("vo");
-    check(ctx.set_option("osc".into(), Node::Flag(true)));
-    println!("osc");
-    check(ctx.initialize());
     println!("init");
     let args = std::env::args().collect::<Vec<String>>();
 
-    let res = ctx.command(vec!["loadfile".into(), args[1].clone()], false);
-    if let Err(err) = res {
-        check(Some(err));
+    if let Err(error) = ctx.command(vec!["loadfile".into(), args[1].clone()], false) {
+        panic!("mpv API error: {}", error);
     }
 
     loop {
@@ -1,7 +1,9 @@
 pub mod consts;
+#[cfg(feature = "libmpv")]
 pub mod funcs;
 pub mod types;
 
 pub use consts::*;
+#[cfg(feature = "libmpv")]
 pub use funcs::*;
 pub use types::*;
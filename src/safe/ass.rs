@@ -0,0 +1,239 @@
+use std::fmt::Write;
+
+#[cfg(feature = "libmpv")]
+use super::{client::MpvHandle, error::Error, subtitle::SubtitleFlags};
+
+/// Builds ASS event text with override tags and vector drawings, for
+/// `OsdOverlay` or for subtitles added with `sub_add_ass()`.
+///
+/// Every event is a line of its own. Text is escaped, so it is shown as given.
+/// Colors are given as RGB, and converted to ASS's BGR order. Alpha values are
+/// ASS's: 0 is opaque and 255 fully transparent.
+///
+/// Coordinates are in the overlay's or script's resolution.
+#[derive(Debug, Clone, Default)]
+pub struct AssBuilder {
+    events: Vec<String>,
+    drawing: bool,
+}
+
+impl AssBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new event. Tags don't carry over from one event to the next.
+    pub fn new_event(mut self) -> Self {
+        self.end_drawing();
+        self.events.push(String::new());
+        self
+    }
+
+    fn current(&mut self) -> &mut String {
+        if self.events.is_empty() {
+            self.events.push(String::new());
+        }
+        self.events.last_mut().unwrap()
+    }
+
+    fn end_drawing(&mut self) {
+        if self.drawing {
+            self.drawing = false;
+            self.current().push_str("{\\p0}");
+        }
+    }
+
+    fn tag(mut self, tag: std::fmt::Arguments) -> Self {
+        let event = self.current();
+        event.push('{');
+        let _ = event.write_fmt(tag);
+        event.push('}');
+        self
+    }
+
+    /// Add override tags as written, without escaping, e.g. `\fscx150`.
+    pub fn raw_tags(self, tags: &str) -> Self {
+        self.tag(format_args!("{tags}"))
+    }
+
+    /// Position the event at (`x`, `y`), relative to its alignment.
+    pub fn pos(self, x: f64, y: f64) -> Self {
+        self.tag(format_args!("\\pos({x},{y})"))
+    }
+
+    /// Alignment as on a numpad: 1 is bottom left, 5 centered, 9 top right.
+    pub fn align(self, alignment: u8) -> Self {
+        self.tag(format_args!("\\an{}", alignment.clamp(1, 9)))
+    }
+
+    /// Fill color of text and drawings.
+    pub fn color(self, r: u8, g: u8, b: u8) -> Self {
+        self.tag(format_args!("\\1c&H{b:02X}{g:02X}{r:02X}&"))
+    }
+
+    pub fn border_color(self, r: u8, g: u8, b: u8) -> Self {
+        self.tag(format_args!("\\3c&H{b:02X}{g:02X}{r:02X}&"))
+    }
+
+    pub fn shadow_color(self, r: u8, g: u8, b: u8) -> Self {
+        self.tag(format_args!("\\4c&H{b:02X}{g:02X}{r:02X}&"))
+    }
+
+    /// Alpha of fill, border and shadow.
+    pub fn alpha(self, alpha: u8) -> Self {
+        self.tag(format_args!("\\alpha&H{alpha:02X}&"))
+    }
+
+    /// Alpha of the fill only.
+    pub fn fill_alpha(self, alpha: u8) -> Self {
+        self.tag(format_args!("\\1a&H{alpha:02X}&"))
+    }
+
+    /// Font name. Characters that would end the tag are removed.
+    pub fn font(self, name: &str) -> Self {
+        let name = name.replace(['\\', '{', '}'], "");
+        self.tag(format_args!("\\fn{name}"))
+    }
+
+    pub fn font_size(self, size: f64) -> Self {
+        self.tag(format_args!("\\fs{size}"))
+    }
+
+    pub fn bold(self, enable: bool) -> Self {
+        self.tag(format_args!("\\b{}", enable as u8))
+    }
+
+    pub fn italic(self, enable: bool) -> Self {
+        self.tag(format_args!("\\i{}", enable as u8))
+    }
+
+    /// Width of the border around text and drawings.
+    pub fn border(self, width: f64) -> Self {
+        self.tag(format_args!("\\bord{width}"))
+    }
+
+    /// Distance of the shadow.
+    pub fn shadow(self, depth: f64) -> Self {
+        self.tag(format_args!("\\shad{depth}"))
+    }
+
+    /// Blur the edges of text and drawings.
+    pub fn blur(self, strength: f64) -> Self {
+        self.tag(format_args!("\\blur{strength}"))
+    }
+
+    /// Add text, escaped so that it is shown literally. Newlines become line
+    /// breaks.
+    pub fn text(mut self, text: &str) -> Self {
+        self.end_drawing();
+        escape_into(self.current(), text);
+        self
+    }
+
+    fn draw(mut self, cmd: std::fmt::Arguments) -> Self {
+        let separate = self.drawing;
+        if !self.drawing {
+            self = self.raw_tags("\\p1");
+            self.drawing = true;
+        }
+        let event = self.current();
+        if separate {
+            event.push(' ');
+        }
+        let _ = event.write_fmt(cmd);
+        self
+    }
+
+    /// Start a new shape of the drawing at (`x`, `y`). Shapes are closed and
+    /// filled.
+    pub fn move_to(self, x: f64, y: f64) -> Self {
+        self.draw(format_args!("m {x} {y}"))
+    }
+
+    pub fn line_to(self, x: f64, y: f64) -> Self {
+        self.draw(format_args!("l {x} {y}"))
+    }
+
+    /// Cubic bezier curve from the current point to (`x3`, `y3`), with two
+    /// control points.
+    pub fn bezier_to(self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) -> Self {
+        self.draw(format_args!("b {x1} {y1} {x2} {y2} {x3} {y3}"))
+    }
+
+    /// A filled rectangle from (`x0`, `y0`) to (`x1`, `y1`).
+    pub fn rect(self, x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        self.move_to(x0, y0)
+            .line_to(x1, y0)
+            .line_to(x1, y1)
+            .line_to(x0, y1)
+    }
+
+    /// The events, one per line, as expected by `OsdOverlay::data`.
+    pub fn build(mut self) -> String {
+        self.end_drawing();
+        self.events.join("\n")
+    }
+
+    /// A complete ASS script showing the events from `start` to `end`
+    /// seconds, for `sub_add_ass()`. Coordinates are in `res_x` by `res_y`.
+    pub fn build_script(self, res_x: u32, res_y: u32, start: f64, end: f64) -> String {
+        let mut script = format!(
+            "[Script Info]\nScriptType: v4.00+\nPlayResX: {res_x}\nPlayResY: {res_y}\n\n\
+             [V4+ Styles]\n\
+             Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, \
+             BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, \
+             BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+             Style: Default,sans-serif,{},&H00FFFFFF,&H00FFFFFF,&H00000000,&H00000000,\
+             0,0,0,0,100,100,0,0,1,0,0,7,0,0,0,1\n\n\
+             [Events]\n\
+             Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+            (res_y / 20).max(1)
+        );
+        let (start, end) = (ass_timestamp(start), ass_timestamp(end));
+        for event in self.build().lines() {
+            let _ = writeln!(script, "Dialogue: 0,{start},{end},Default,,0,0,0,,{event}");
+        }
+        script
+    }
+}
+
+/// Escape text like mpv does for OSD messages: "{" would start override
+/// tags, and a backslash followed by certain letters is a special character.
+fn escape_into(dst: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '{' => dst.push_str("\\{"),
+            '\n' => dst.push_str("\\N"),
+            // A WORD JOINER after the backslash breaks escapes like "\N".
+            '\\' => dst.push_str("\\\u{2060}"),
+            c => dst.push(c),
+        }
+    }
+}
+
+/// Escape text for ASS. See `AssBuilder::text()`.
+pub fn ass_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    escape_into(&mut escaped, text);
+    escaped
+}
+
+fn ass_timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        centis / 6000 % 60,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+#[cfg(feature = "libmpv")]
+impl MpvHandle {
+    /// Add an ASS script, e.g. from `AssBuilder::build_script()`, as subtitle
+    /// track of the current file, and select it.
+    pub fn sub_add_ass(&mut self, script: &str, title: &str) -> Result<(), Error> {
+        self.add_subtitle_text(script, SubtitleFlags::Select, Some(title), None)
+    }
+}
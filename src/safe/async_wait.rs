@@ -0,0 +1,116 @@
+use std::{
+    ffi::c_void,
+    future::Future,
+    pin::Pin,
+    ptr::null_mut,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::raw::mpv_set_wakeup_callback;
+
+use super::{
+    client::MpvHandle,
+    error::Error,
+    event::Event,
+    player::{wait_error, LoadWait, Player},
+};
+
+/// The waker of the task waiting for events, woken by mpv's wakeup callback.
+type WakerSlot = Mutex<Option<Waker>>;
+
+unsafe extern "C" fn wake(d: *mut c_void) {
+    let slot = unsafe { &*(d as *const WakerSlot) };
+    if let Some(waker) = slot.lock().unwrap().take() {
+        waker.wake();
+    }
+}
+
+/// Future of `MpvHandle::next_event()`.
+///
+/// While it's pending, it is the handle's wakeup callback (see
+/// `mpv_set_wakeup_callback()`), replacing any other, unless the handle
+/// buffers events (see `MpvHandle::buffer_events()`).
+pub struct NextEvent<'a> {
+    ctx: &'a mut MpvHandle,
+    slot: Option<Arc<WakerSlot>>,
+}
+
+impl Future for NextEvent<'_> {
+    type Output = Event;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Event> {
+        let this = self.get_mut();
+        // Store the waker before looking for events, so an event queued in
+        // between isn't missed.
+        if let Some(buffer) = this.ctx.event_buffer() {
+            buffer.set_waker(cx.waker().clone());
+        } else {
+            let slot = this.slot.get_or_insert_with(|| {
+                let slot = Arc::new(WakerSlot::new(None));
+                let d = Arc::as_ptr(&slot) as *mut c_void;
+                unsafe { mpv_set_wakeup_callback(this.ctx.as_raw(), Some(wake), d) };
+                slot
+            });
+            *slot.lock().unwrap() = Some(cx.waker().clone());
+        }
+        match this.ctx.wait_event(0.0) {
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for NextEvent<'_> {
+    fn drop(&mut self) {
+        if self.slot.is_some() {
+            // mpv holds a lock while it calls the callback, so it's not running
+            // anymore once this returns, and the slot can be freed.
+            unsafe { mpv_set_wakeup_callback(self.ctx.as_raw(), None, null_mut()) };
+        }
+    }
+}
+
+/// Async versions of the waits of `Player`, for use with any executor. They
+/// don't take a timeout; use the executor's (e.g. `tokio::time::timeout()`)
+/// instead.
+impl MpvHandle {
+    /// Wait for the next event without blocking the thread.
+    pub fn next_event(&mut self) -> NextEvent<'_> {
+        NextEvent {
+            ctx: self,
+            slot: None,
+        }
+    }
+
+    /// Async version of `Player::wait_for()`.
+    pub async fn wait_for_async(
+        &mut self,
+        mut pred: impl FnMut(&Event) -> bool,
+    ) -> Result<Event, Error> {
+        loop {
+            let event = self.next_event().await;
+            if pred(&event) {
+                return Ok(event);
+            }
+            if let Some(err) = wait_error(&event) {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Async version of `Player::load_and_wait()`.
+    pub async fn load_and_wait_async(&mut self, url: &str) -> Result<(), Error> {
+        let res = Player::command(self, vec!["loadfile".into(), url.into()], true)?;
+        let mut load = LoadWait::new(res);
+        loop {
+            let event = self
+                .wait_for_async(LoadWait::wants)
+                .await
+                .map_err(|err| Error::new(LoadWait::operation(), err.code))?;
+            if let Some(res) = load.step(event) {
+                return res;
+            }
+        }
+    }
+}
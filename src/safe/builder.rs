@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use super::{
+    client::MpvHandle,
+    error::{Error, MpvError, Operation},
+    node::Node,
+};
+
+/// Values of the "idle" option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idle {
+    /// Quit when there is nothing left to play.
+    No,
+    /// Wait for new commands when there is nothing left to play.
+    Yes,
+    /// Only idle at start up, if no file was given.
+    Once,
+}
+
+impl Idle {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::No => "no",
+            Self::Yes => "yes",
+            Self::Once => "once",
+        }
+    }
+}
+
+/// Values of the "keep-open" option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepOpen {
+    /// Move on to the next file, or stop, at the end of a file.
+    No,
+    /// Pause at the end of the last file of the playlist.
+    Yes,
+    /// Pause at the end of every file.
+    Always,
+}
+
+impl KeepOpen {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::No => "no",
+            Self::Yes => "yes",
+            Self::Always => "always",
+        }
+    }
+}
+
+/// Values of the "cache" option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cache {
+    No,
+    Yes,
+    /// Enable the cache for network streams only.
+    Auto,
+}
+
+impl Cache {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::No => "no",
+            Self::Yes => "yes",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+/// Collects options that have to be set before mpv_initialize(), and creates
+/// an initialized `MpvHandle` from them.
+///
+/// Options are applied in the order they were first given. Setting the same
+/// option twice keeps the last value.
+#[derive(Debug, Clone, Default)]
+pub struct MpvBuilder {
+    options: Vec<(String, Node)>,
+    buffer_events: Option<usize>,
+}
+
+impl MpvBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A builder for an instance without any audio or video output, suitable
+    /// for tests and servers without display or sound card: `vo=null`,
+    /// `ao=null`, `terminal=no`, `idle=yes` and no config files.
+    ///
+    /// Further options can be added as usual.
+    pub fn headless() -> Self {
+        Self::new()
+            .vo("null")
+            .ao("null")
+            .terminal(false)
+            .idle(Idle::Yes)
+            .config(false)
+    }
+
+    /// Set an arbitrary option. The name is the same as on the mpv command
+    /// line, but without the leading "--".
+    pub fn option(mut self, name: impl Into<String>, value: impl Into<Node>) -> Self {
+        let name = name.into();
+        let value = value.into();
+        match self.options.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.options.push((name, value)),
+        }
+        self
+    }
+
+    /// Video output driver, e.g. "gpu", "libmpv" or "null".
+    pub fn vo(self, vo: impl Into<String>) -> Self {
+        self.option("vo", vo.into())
+    }
+
+    /// Audio output driver, e.g. "pulse", "alsa" or "null".
+    pub fn ao(self, ao: impl Into<String>) -> Self {
+        self.option("ao", ao.into())
+    }
+
+    /// Hardware decoding API, e.g. "no", "auto-safe" or "vaapi".
+    pub fn hwdec(self, hwdec: impl Into<String>) -> Self {
+        self.option("hwdec", hwdec.into())
+    }
+
+    /// Whether config files are loaded during initialization. libmpv does not
+    /// load them by default. If you enable this, you should set `config_dir`
+    /// too, otherwise the command line player's config is used.
+    pub fn config(self, enable: bool) -> Self {
+        self.option("config", enable)
+    }
+
+    /// Root directory for config files.
+    pub fn config_dir(self, path: impl AsRef<Path>) -> Self {
+        self.option("config-dir", path.as_ref().to_string_lossy().into_owned())
+    }
+
+    /// Whether mpv may use the terminal (stdin/stdout/stderr).
+    pub fn terminal(self, enable: bool) -> Self {
+        self.option("terminal", enable)
+    }
+
+    /// Whether mpv's default key bindings are active.
+    pub fn input_default_bindings(self, enable: bool) -> Self {
+        self.option("input-default-bindings", enable)
+    }
+
+    /// Whether the video window accepts keyboard input.
+    pub fn input_vo_keyboard(self, enable: bool) -> Self {
+        self.option("input-vo-keyboard", enable)
+    }
+
+    /// Whether the on screen controller is shown.
+    pub fn osc(self, enable: bool) -> Self {
+        self.option("osc", enable)
+    }
+
+    pub fn idle(self, idle: Idle) -> Self {
+        self.option("idle", idle.as_str())
+    }
+
+    pub fn keep_open(self, keep_open: KeepOpen) -> Self {
+        self.option("keep-open", keep_open.as_str())
+    }
+
+    pub fn cache(self, cache: Cache) -> Self {
+        self.option("cache", cache.as_str())
+    }
+
+    /// Whether URLs are resolved with youtube-dl/yt-dlp.
+    pub fn ytdl(self, enable: bool) -> Self {
+        self.option("ytdl", enable)
+    }
+
+    /// Start a thread that reads events ahead into a buffer of `size` events,
+    /// in addition to mpv's own event queue, whose size is fixed. Fewer events
+    /// are lost then when they aren't read quickly. This doesn't change mpv's
+    /// queue; see `MpvHandle::buffer_events()`.
+    pub fn buffer_events(mut self, size: usize) -> Self {
+        self.buffer_events = Some(size);
+        self
+    }
+
+    /// Create the mpv instance, set all options and initialize it.
+    ///
+    /// Every option is tried even if an earlier one failed. On failure, all
+    /// errors are returned, and the instance is destroyed. Initialization is
+    /// not attempted if any option failed.
+    pub fn build(self) -> Result<MpvHandle, Vec<Error>> {
+        let Some(mut ctx) = MpvHandle::new() else {
+            return Err(vec![Error::new(Operation::Create, MpvError::NoMemory)]);
+        };
+        let errors = self
+            .options
+            .into_iter()
+            .filter_map(|(name, value)| ctx.try_set_option(name, value).err())
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        ctx.try_initialize().map_err(|err| vec![err])?;
+        if let Some(size) = self.buffer_events {
+            ctx.buffer_events(size);
+        }
+        Ok(ctx)
+    }
+}
+
+impl MpvHandle {
+    /// Create an initialized headless instance (see `MpvBuilder::headless()`).
+    ///
+    /// Panics if the instance can't be created, which is what a test wants.
+    pub fn for_tests() -> Self {
+        match MpvBuilder::headless().build() {
+            Ok(ctx) => ctx,
+            Err(errors) => {
+                let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                panic!(
+                    "failed to create headless mpv instance: {}",
+                    errors.join("; ")
+                );
+            }
+        }
+    }
+}
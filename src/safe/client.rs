@@ -1,23 +1,66 @@
 use std::{
     ffi::{CStr, CString},
+    mem,
     path::Path,
-    ptr::null_mut,
+    ptr::{null, null_mut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError, Weak,
+    },
 };
 
-use libc::free;
+use libc::c_char;
 
 use crate::raw::*;
 
-use super::{error::MpvError, event::Event, node::Node, util::make_rust_string_const};
+use super::{
+    error::{Error, MpvError, Operation},
+    event::Event,
+    event_thread::{Pump, Pumped},
+    node::{free_mpv_node, MpvFormat, Node},
+    player::Player,
+    resync::Resync,
+    util::{command_name, deadline, make_rust_string_const, time_left},
+};
 
 /// Return the MPV_CLIENT_API_VERSION the mpv source has been compiled with.
 pub fn client_api_version() -> u64 {
     unsafe { mpv_client_api_version() }
 }
 
-pub struct MpvHandle(*mut mpv_handle);
+pub struct MpvHandle {
+    ctx: *mut mpv_handle,
+    /// Observed properties, to read again after events were lost.
+    resync: Resync,
+    /// Events read ahead by a thread (see `buffer_events()`).
+    buffer: Option<Pump>,
+    /// Weak clients to destroy before this handle (see `WeakClient`).
+    weak_clients: Vec<Weak<WeakClientState>>,
+}
+
+// SAFETY: client.h states that "the client API is generally fully thread-safe,
+// unless otherwise noted", and that concurrent calls on the same handle are
+// allowed. The exceptions are mpv_wait_event(), which only one thread may call
+// on a handle at a time, and the destruction functions, which must not race
+// with other calls on the handle. Both take `&mut self` or `self` here, so the
+// borrow checker rules that out; everything callable through `&self` is safe
+// to call concurrently. The same goes for the Rust side state, which is only
+// changed through `&mut self`, and the event buffer's receiver, which is only
+// used through it.
+unsafe impl Send for MpvHandle {}
+// SAFETY: see above.
+unsafe impl Sync for MpvHandle {}
 
 impl MpvHandle {
+    fn from_raw(ctx: *mut mpv_handle) -> Self {
+        Self {
+            ctx,
+            resync: Resync::default(),
+            buffer: None,
+            weak_clients: vec![],
+        }
+    }
+
     /// Create a new mpv instance and an associated client API handle to control
     /// the mpv instance. This instance is in a pre-initialized state,
     /// and needs to be initialized to be actually used with most other API
@@ -75,7 +118,7 @@ impl MpvHandle {
         if ctx == null_mut() {
             None
         } else {
-            Some(Self(ctx))
+            Some(Self::from_raw(ctx))
         }
     }
 
@@ -85,7 +128,7 @@ impl MpvHandle {
     /// @return The client name. The string is read-only and is valid until the
     ///         mpv_handle is destroyed.
     pub fn name(&self) -> String {
-        make_rust_string_const(unsafe { mpv_client_name(self.0) }).unwrap()
+        make_rust_string_const(unsafe { mpv_client_name(self.ctx) }).unwrap()
     }
 
     /// Return the ID of this client handle. Every client has its own unique ID. This
@@ -102,7 +145,28 @@ impl MpvHandle {
     ///
     /// @return The client ID.
     pub fn id(&self) -> i64 {
-        unsafe { mpv_client_id(self.0) }
+        unsafe { mpv_client_id(self.ctx) }
+    }
+
+    /// Return the internal time in nanoseconds. This has an arbitrary start offset,
+    /// but will never wrap or go backwards.
+    ///
+    /// Note that this is always the real time, and doesn't necessarily have to do
+    /// with playback time. For example, playback could go faster or slower due to
+    /// playback speed, or due to playback being paused. Use the \"time-pos\" property
+    /// instead to get the playback status.
+    ///
+    /// Unlike other libmpv APIs, this can be called at absolutely any time (even
+    /// within wakeup callbacks), as long as the context is valid.
+    ///
+    /// Safe to be called from mpv render API threads.
+    pub fn get_time_ns(&self) -> i64 {
+        unsafe { mpv_get_time_ns(self.ctx) }
+    }
+
+    /// Same as mpv_get_time_ns but in microseconds.
+    pub fn get_time_us(&self) -> i64 {
+        unsafe { mpv_get_time_us(self.ctx) }
     }
 
     /// Initialize an uninitialized mpv instance. If the mpv instance is already
@@ -121,9 +185,10 @@ impl MpvHandle {
     ///        - player-operation-mode
     ///        - input-app-events (macOS)
     ///      - all encoding mode options
-    pub fn initialize(&mut self) -> Option<MpvError> {
-        let status = unsafe { mpv_initialize(self.0) };
-        MpvError::from_mpv_error(status)
+    #[must_use = "mpv errors are reported through the returned Result"]
+    pub fn try_initialize(&mut self) -> Result<(), Error> {
+        let status = unsafe { mpv_initialize(self.ctx) };
+        MpvError::check(status).map_err(|err| Error::new(Operation::Initialize, err))
     }
 
     /// Similar to mpv_destroy(), but brings the player and all clients down
@@ -149,9 +214,13 @@ impl MpvHandle {
     ///  If this is called on a mpv_handle that was not created with mpv_create(),
     ///  this function will merely send a quit command and then call
     ///  mpv_destroy(), without waiting for the actual shutdown.
-    pub fn terminate(self) {
-        unsafe { mpv_terminate_destroy(self.0) };
-        std::mem::forget(self);
+    pub fn terminate(mut self) {
+        // The buffer's thread must be done with the handle first, and weak
+        // clients would keep this waiting.
+        self.buffer = None;
+        self.destroy_weak_clients();
+        unsafe { mpv_terminate_destroy(self.ctx) };
+        self.ctx = null_mut();
     }
 
     /// Create a new client handle connected to the same player core as ctx. This
@@ -170,11 +239,11 @@ impl MpvHandle {
     ///
     /// Because API requires name to be const pointer, it has to be static CStr to avoid leaks
     pub fn create_client(&mut self, name: &'static CStr) -> Option<MpvHandle> {
-        let ctx = unsafe { mpv_create_client(self.0, name.as_ptr()) };
+        let ctx = unsafe { mpv_create_client(self.ctx, name.as_ptr()) };
         if ctx == null_mut() {
             None
         } else {
-            Some(Self(ctx))
+            Some(Self::from_raw(ctx))
         }
     }
 
@@ -189,11 +258,52 @@ impl MpvHandle {
     /// mpv_terminate_destroy() _and_ mpv_destroy() for the last non-weak
     /// mpv_handle will block until all weak mpv_handles are destroyed.
     pub fn create_weak_client(&mut self, name: &'static CStr) -> Option<MpvHandle> {
-        let ctx = unsafe { mpv_create_weak_client(self.0, name.as_ptr()) };
+        let ctx = unsafe { mpv_create_weak_client(self.ctx, name.as_ptr()) };
         if ctx == null_mut() {
             None
         } else {
-            Some(Self(ctx))
+            Some(Self::from_raw(ctx))
+        }
+    }
+
+    /// Create a weak client for a helper like `Navigator`, which is destroyed
+    /// together with this handle.
+    pub(crate) fn create_tied_client(&mut self, name: &'static CStr) -> Result<WeakClient, Error> {
+        let Some(handle) = self.create_weak_client(name) else {
+            return Err(Error::new(Operation::Create, MpvError::NoMemory));
+        };
+        let state = Arc::new(WeakClientState {
+            raw: handle.ctx,
+            closing: AtomicBool::new(false),
+            handle: Mutex::new(Some(handle)),
+            on_close: Mutex::new(None),
+        });
+        self.weak_clients.retain(|client| client.strong_count() > 0);
+        self.weak_clients.push(Arc::downgrade(&state));
+        Ok(WeakClient(state))
+    }
+
+    fn destroy_weak_clients(&mut self) {
+        for client in self.weak_clients.drain(..) {
+            let Some(state) = client.upgrade() else {
+                continue;
+            };
+            state.closing.store(true, Ordering::SeqCst);
+            // The handle is only taken out below, so it's still alive.
+            unsafe { mpv_wakeup(state.raw) };
+            let handle = state
+                .handle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            let on_close = state
+                .on_close
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let (Some(mut handle), Some(args)) = (handle, on_close) {
+                let _ = handle.command(args, false);
+            }
         }
     }
 
@@ -213,12 +323,14 @@ impl MpvHandle {
     ///
     /// @param filename absolute path to the config file on the local filesystem
     /// @return error code
-    pub fn load_config_file(&mut self, filename: &Path) -> Option<MpvError> {
-        let path = CString::into_raw(CString::new(filename.to_path_buf().to_str()?).unwrap());
-        let status = unsafe { mpv_load_config_file(self.0, path as _) };
-        let res = MpvError::from_mpv_error(status);
-        unsafe { free(path as _) };
-        res
+    #[must_use = "mpv errors are reported through the returned Result"]
+    pub fn try_load_config_file(&mut self, filename: &Path) -> Result<(), Error> {
+        let error = |err| Error::new(Operation::LoadConfigFile(filename.to_path_buf()), err);
+        let Some(path) = filename.to_str().and_then(|path| CString::new(path).ok()) else {
+            return Err(error(MpvError::InvalidParameter));
+        };
+        let status = unsafe { mpv_load_config_file(self.ctx, path.as_ptr()) };
+        MpvError::check(status).map_err(error)
     }
 
     /// Set an option. Note that you can't normally set options during runtime. It
@@ -243,19 +355,46 @@ impl MpvHandle {
     /// @param format see enum mpv_format.
     /// @param[in] data Option value (according to the format).
     /// @return error code
-    pub fn set_option(&mut self, name: String, node: Node) -> Option<MpvError> {
-        let name = CString::into_raw(CString::new(name.clone()).unwrap());
-        let Some(raw) = node.to_mpv_node() else {
-            return Some(MpvError::OptionError);
+    #[must_use = "mpv errors are reported through the returned Result"]
+    pub fn try_set_option(&mut self, name: String, node: Node) -> Result<(), Error> {
+        let operation = Operation::SetOption(name.clone());
+        let Ok(name) = CString::new(name) else {
+            return Err(Error::new(operation, MpvError::OptionNotFound));
+        };
+        let Some(mut raw) = node.to_mpv_node() else {
+            return Err(Error::new(operation, MpvError::OptionError));
         };
 
-        let data = Box::into_raw(Box::new(raw.u.clone()));
-        let status = unsafe { mpv_set_option(self.0, name as _, raw.format, data as *mut _) };
-        unsafe {
-            free(name as _);
-            free(data as _);
-        }
-        MpvError::from_mpv_error(status)
+        let status = unsafe {
+            mpv_set_option(
+                self.ctx,
+                name.as_ptr(),
+                mpv_format_MPV_FORMAT_NODE,
+                &mut raw as *mut mpv_node as *mut _,
+            )
+        };
+        free_mpv_node(raw);
+        MpvError::check(status).map_err(|err| Error::new(operation, err))
+    }
+
+    /// Old form of `try_initialize()`, where `None` means success.
+    #[deprecated(note = "use `try_initialize`, which returns a `Result`")]
+    pub fn initialize(&mut self) -> Option<MpvError> {
+        self.try_initialize().err().map(MpvError::from)
+    }
+
+    /// Old form of `try_load_config_file()`, where `None` means success.
+    #[deprecated(note = "use `try_load_config_file`, which returns a `Result`")]
+    pub fn load_config_file(&mut self, filename: &Path) -> Option<MpvError> {
+        self.try_load_config_file(filename)
+            .err()
+            .map(MpvError::from)
+    }
+
+    /// Old form of `try_set_option()`, where `None` means success.
+    #[deprecated(note = "use `try_set_option`, which returns a `Result`")]
+    pub fn set_option(&mut self, name: String, node: Node) -> Option<MpvError> {
+        self.try_set_option(name, node).err().map(MpvError::from)
     }
 
     /// Send a command to the player. Commands are the same as those used in
@@ -286,38 +425,48 @@ impl MpvHandle {
         &mut self,
         args: Vec<String>,
         require_result: bool,
-    ) -> Result<Option<Node>, MpvError> {
-        let mut args = args
+    ) -> Result<Option<Node>, Error> {
+        let operation = Operation::Command(args.first().cloned().unwrap_or_default());
+        let Ok(args) = args
+            .into_iter()
+            .map(CString::new)
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            return Err(Error::new(operation, MpvError::InvalidParameter));
+        };
+        let mut ptrs = args
             .iter()
-            .map(|s| CString::into_raw(CString::new(s.clone()).unwrap()))
-            .collect::<Vec<*mut i8>>();
-        args.push(null_mut());
-        let args = args.leak().as_mut_ptr();
-        let res = if require_result {
-            let result = Box::into_raw(Box::new(mpv_node {
-                format: 0,
-                u: mpv_node__bindgen_ty_1 { flag: 0 },
-            }));
-
-            let status = unsafe { mpv_command_ret(self.0, args as *mut _, result) };
-
-            let res = match MpvError::from_mpv_error(status) {
-                Some(err) => Err(err),
-                None => Ok(Node::from_mpv_node(unsafe { *result })),
-            };
+            .map(|s| s.as_ptr())
+            .collect::<Vec<*const c_char>>();
+        ptrs.push(null());
 
-            unsafe { mpv_free_node_contents(result) };
-            res
+        if require_result {
+            let mut result = empty_node();
+            let status = unsafe { mpv_command_ret(self.ctx, ptrs.as_mut_ptr(), &mut result) };
+            MpvError::check(status).map_err(|err| Error::new(operation, err))?;
+
+            let res = Node::from_mpv_node(result);
+            unsafe { mpv_free_node_contents(&mut result) };
+            Ok(res)
         } else {
-            let status = unsafe { mpv_command(self.0, args as *mut _) };
+            let status = unsafe { mpv_command(self.ctx, ptrs.as_mut_ptr()) };
+            MpvError::check(status).map_err(|err| Error::new(operation, err))?;
+            Ok(None)
+        }
+    }
 
-            match MpvError::from_mpv_error(status) {
-                Some(err) => Err(err),
-                None => Ok(None),
-            }
+    /// Same as mpv_command, but use input.conf parsing for splitting arguments.
+    /// This is slightly simpler, but also more error prone, since arguments may
+    /// need quoting/escaping.
+    ///
+    /// This also has OSD and string expansion enabled by default.
+    pub fn command_string(&mut self, args: &str) -> Result<(), Error> {
+        let operation = Operation::Command(args.split_whitespace().next().unwrap_or("").into());
+        let Ok(args) = CString::new(args) else {
+            return Err(Error::new(operation, MpvError::InvalidParameter));
         };
-        free_array(args);
-        res
+        let status = unsafe { mpv_command_string(self.ctx, args.as_ptr()) };
+        MpvError::check(status).map_err(|err| Error::new(operation, err))
     }
 
     /// Same as mpv_command(), but allows passing structured data in any format.
@@ -352,41 +501,315 @@ impl MpvHandle {
     ///                    (again, only if the command actually succeeds).
     ///                    Not many commands actually use this at all.
     /// @return error code (the result parameter is not set on error)
-    pub fn command_node(
-        &mut self,
-        arg: Node,
-        require_result: bool,
-    ) -> Result<Option<Node>, MpvError> {
-        let Some(args) = arg.to_mpv_node() else {
-            return Err(MpvError::CommandError);
+    pub fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error> {
+        let operation = Operation::Command(command_name(&arg));
+        let Some(mut args) = arg.to_mpv_node() else {
+            return Err(Error::new(operation, MpvError::CommandError));
         };
-        let args = Box::into_raw(Box::new(args));
-        let result = if require_result {
-            Box::into_raw(Box::new(mpv_node {
-                format: 0,
-                u: mpv_node__bindgen_ty_1 { flag: 0 },
-            }))
+        let mut result = empty_node();
+        let result_ptr = if require_result {
+            &mut result as *mut mpv_node
         } else {
             null_mut()
         };
 
-        let status = unsafe { mpv_command_node(self.0, args, result) };
+        let status = unsafe { mpv_command_node(self.ctx, &mut args, result_ptr) };
+        free_mpv_node(args);
+        MpvError::check(status).map_err(|err| Error::new(operation, err))?;
 
-        let res = match MpvError::from_mpv_error(status) {
-            Some(err) => Err(err),
-            None => {
-                if require_result {
-                    Ok(Node::from_mpv_node(unsafe { *result }))
-                } else {
-                    Ok(None)
-                }
-            }
-        };
-        unsafe { mpv_free_node_contents(args) };
         if require_result {
-            unsafe { mpv_free_node_contents(result) };
+            let res = Node::from_mpv_node(result);
+            unsafe { mpv_free_node_contents(&mut result) };
+            Ok(res)
+        } else {
+            Ok(None)
         }
-        res
+    }
+
+    /// Same as mpv_command_node(), but run it asynchronously. Basically, this
+    /// function is to mpv_command_node() what mpv_command_async() is to
+    /// mpv_command().
+    ///
+    /// The result of the command is delivered as MPV_EVENT_COMMAND_REPLY, with
+    /// the given reply_userdata. You will always get a reply event, unless this
+    /// call itself fails (i.e. parsing or queuing the command fails).
+    ///
+    /// Safe to be called from mpv render API threads.
+    ///
+    /// @param reply_userdata the value mpv_event.reply_userdata of the reply will
+    ///                       be set to (see section about asynchronous calls)
+    /// @param args as in mpv_command_node()
+    /// @return error code (if parsing or queuing the command fails)
+    pub fn command_node_async(&mut self, arg: Node, reply_userdata: u64) -> Result<(), Error> {
+        let operation = Operation::Command(command_name(&arg));
+        let Some(mut args) = arg.to_mpv_node() else {
+            return Err(Error::new(operation, MpvError::CommandError));
+        };
+        let status = unsafe { mpv_command_node_async(self.ctx, reply_userdata, &mut args) };
+        free_mpv_node(args);
+        MpvError::check(status).map_err(|err| Error::new(operation, err))
+    }
+
+    /// Signal to all async requests with the matching ID to abort. This affects
+    /// the following API calls:
+    ///
+    ///      mpv_command_async
+    ///      mpv_command_node_async
+    ///
+    /// This API function is mostly asynchronous itself. It will not wait until the
+    /// command is aborted. Instead, the command will terminate as usual, but with
+    /// some work not done. Not all commands may support this functionality.
+    ///
+    /// @param reply_userdata ID of the request to be aborted (see above)
+    pub fn abort_async_command(&mut self, reply_userdata: u64) {
+        unsafe { mpv_abort_async_command(self.ctx, reply_userdata) };
+    }
+
+    /// Set a property. The node is passed to mpv as MPV_FORMAT_NODE, so mpv
+    /// converts it to the property's type (e.g. a string "yes" works for flag
+    /// properties).
+    ///
+    /// The property is set synchronously: once this returns, the change has been
+    /// applied (or failed). Changes made by other clients or the core may still
+    /// arrive later as property change events.
+    ///
+    /// @param name The property name. See input.rst for a list of properties.
+    pub fn set_property(&mut self, name: String, node: Node) -> Result<(), Error> {
+        let operation = Operation::SetProperty(name.clone());
+        let Ok(name) = CString::new(name) else {
+            return Err(Error::new(operation, MpvError::PropertyNotFound));
+        };
+        let Some(mut raw) = node.to_mpv_node() else {
+            return Err(Error::new(operation, MpvError::PropertyError));
+        };
+
+        let status = unsafe {
+            mpv_set_property(
+                self.ctx,
+                name.as_ptr(),
+                mpv_format_MPV_FORMAT_NODE,
+                &mut raw as *mut mpv_node as *mut _,
+            )
+        };
+        free_mpv_node(raw);
+        MpvError::check(status).map_err(|err| Error::new(operation, err))
+    }
+
+    /// Read the value of the given property, as MPV_FORMAT_NODE.
+    ///
+    /// If the property has no value (MPV_FORMAT_NONE), MPV_ERROR_PROPERTY_UNAVAILABLE
+    /// is returned.
+    ///
+    /// @param name The property name.
+    pub fn get_property(&mut self, name: String) -> Result<Node, Error> {
+        let operation = Operation::GetProperty(name.clone());
+        let Ok(name) = CString::new(name) else {
+            return Err(Error::new(operation, MpvError::PropertyNotFound));
+        };
+        let mut result = empty_node();
+
+        let status = unsafe {
+            mpv_get_property(
+                self.ctx,
+                name.as_ptr(),
+                mpv_format_MPV_FORMAT_NODE,
+                &mut result as *mut mpv_node as *mut _,
+            )
+        };
+        MpvError::check(status).map_err(|err| Error::new(operation.clone(), err))?;
+
+        let res = Node::from_mpv_node(result);
+        unsafe { mpv_free_node_contents(&mut result) };
+        res.ok_or(Error::new(operation, MpvError::PropertyUnavailable))
+    }
+
+    /// Return the value of the property with the given name as string. This is
+    /// equivalent to mpv_get_property() with MPV_FORMAT_STRING.
+    ///
+    /// See MPV_FORMAT_STRING for character encoding issues.
+    ///
+    /// @param name The property name.
+    pub fn get_property_string(&mut self, name: String) -> Result<String, Error> {
+        let operation = Operation::GetProperty(name.clone());
+        let Ok(name) = CString::new(name) else {
+            return Err(Error::new(operation, MpvError::PropertyNotFound));
+        };
+        let mut result: *mut c_char = null_mut();
+
+        let status = unsafe {
+            mpv_get_property(
+                self.ctx,
+                name.as_ptr(),
+                mpv_format_MPV_FORMAT_STRING,
+                &mut result as *mut *mut c_char as *mut _,
+            )
+        };
+        MpvError::check(status).map_err(|err| Error::new(operation.clone(), err))?;
+
+        let res = make_rust_string_const(result);
+        unsafe { mpv_free(result as *mut _) };
+        res.ok_or(Error::new(operation, MpvError::PropertyError))
+    }
+
+    /// Get a notification whenever the given property changes. You will receive
+    /// updates as MPV_EVENT_PROPERTY_CHANGE. Note that this is not very precise:
+    /// for some properties, it may not send updates even if the property changed.
+    /// This depends on the property, and it's a valid feature request to ask for
+    /// better update handling of a specific property. (For some properties, like
+    /// ``clock``, which shows the wall clock, this mechanism doesn't make too
+    /// much sense anyway.)
+    ///
+    /// Property changes are coalesced: the change events are returned only once the
+    /// event queue becomes empty (e.g. mpv_wait_event() would block or return
+    /// MPV_EVENT_NONE), and then only one event per changed property is returned.
+    ///
+    /// Normally, change events are sent only if the property value changes according
+    /// to the requested format. mpv_event_property will contain the property value
+    /// as data member.
+    ///
+    /// Warning: if a property is unavailable or retrieving it caused an error,
+    ///          MPV_FORMAT_NONE will be set in mpv_event_property, even if the
+    ///          format parameter was set to a different value. In this case, the
+    ///          mpv_event_property.data field is invalid.
+    ///
+    /// If the property is observed with the format parameter set to MPV_FORMAT_NONE,
+    /// you get low-level notifications whether the property _may_ have changed, and
+    /// the data member in mpv_event_property will be unset. With this mode, you
+    /// will have to determine yourself whether the property really changed. On the
+    /// other hand, this mechanism can be faster and uses less resources.
+    ///
+    /// Observing a property that doesn't exist is allowed. (Although it may still
+    /// cause some sporadic change events.)
+    ///
+    /// Keep in mind that you will get change notifications even if you change a
+    /// property yourself. Try to avoid endless feedback loops, which could happen
+    /// if you react to the change notifications triggered by your own change.
+    ///
+    /// Only the mpv_handle on which this was called will receive the property
+    /// change events, or can unobserve them.
+    ///
+    /// @param reply_userdata This will be used for the mpv_event.reply_userdata
+    ///                       field for the received MPV_EVENT_PROPERTY_CHANGE
+    ///                       events. (Also see section about asynchronous calls,
+    ///                       although this function is somewhat different from
+    ///                       actual asynchronous calls.)
+    ///                       If you have no use for this, pass 0.
+    ///                       Also see mpv_unobserve_property().
+    /// @param name The property name.
+    /// @param format see enum mpv_format. Can be MPV_FORMAT_NONE to omit values
+    ///               from the change events.
+    /// @return error code (usually fails only on OOM or unsupported format)
+    pub fn observe_property(
+        &mut self,
+        name: String,
+        format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        let operation = Operation::ObserveProperty(name.clone());
+        let Ok(name) = CString::new(name) else {
+            return Err(Error::new(operation, MpvError::PropertyNotFound));
+        };
+        let status = unsafe {
+            mpv_observe_property(
+                self.ctx,
+                reply_userdata,
+                name.as_ptr(),
+                format.to_mpv_format(),
+            )
+        };
+        MpvError::check(status).map_err(|err| Error::new(operation, err))?;
+        let name = name.into_string().unwrap();
+        self.resync.observe(name, format, reply_userdata);
+        Ok(())
+    }
+
+    /// Undo mpv_observe_property(). This will remove all observed properties for
+    /// which the given number was passed as reply_userdata to mpv_observe_property.
+    ///
+    /// @param registered_reply_userdata ID that was passed to mpv_observe_property
+    /// @return negative value is an error code, >=0 is number of removed properties
+    ///         on success (includes the case when 0 were removed)
+    pub fn unobserve_property(&mut self, registered_reply_userdata: u64) -> Result<usize, Error> {
+        let status = unsafe { mpv_unobserve_property(self.ctx, registered_reply_userdata) };
+        MpvError::check(status.min(0))
+            .map_err(|err| Error::new(Operation::UnobserveProperty, err))?;
+        self.resync.unobserve(registered_reply_userdata);
+        Ok(status as usize)
+    }
+
+    /// Enable or disable receiving of log messages. These are the messages the
+    /// command line player prints to the terminal. This call sets the minimum
+    /// required log level for a message to be received with MPV_EVENT_LOG_MESSAGE.
+    ///
+    /// @param min_level Minimal log level as string. Valid log levels:
+    ///                      no fatal error warn info v debug trace
+    ///                  The value \"no\" disables all messages. This is the default.
+    ///                  An exception is the value \"terminal-default\", which uses the
+    ///                  log level as set by the \"--msg-level\" option. This works
+    ///                  even if the terminal is disabled. (Since API version 1.19.)
+    ///                  Also see mpv_log_level.
+    /// @return error code
+    pub fn request_log_messages(&mut self, min_level: &str) -> Result<(), Error> {
+        let error = |err| Error::new(Operation::RequestLogMessages(min_level.into()), err);
+        let Ok(level) = CString::new(min_level) else {
+            return Err(error(MpvError::InvalidParameter));
+        };
+        let status = unsafe { mpv_request_log_messages(self.ctx, level.as_ptr()) };
+        MpvError::check(status).map_err(error)
+    }
+
+    /// A hook is an event that blocks the player until the client continues it
+    /// with mpv_hook_continue(). Register a hook handler with the given name;
+    /// whenever the hook is invoked, a MPV_EVENT_HOOK with `reply_userdata` is
+    /// sent to this handle.
+    ///
+    /// Handlers are run in order of priority (lowest first), then in order of
+    /// registration. Hooks can't be removed, except by destroying the handle,
+    /// which also continues the hooks it is handling.
+    ///
+    /// See the \"Hooks\" section in the manpage for the defined hooks.
+    pub fn hook_add(
+        &mut self,
+        name: &str,
+        priority: i32,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        let error = |err| Error::new(Operation::HookAdd(name.into()), err);
+        let Ok(c_name) = CString::new(name) else {
+            return Err(error(MpvError::InvalidParameter));
+        };
+        let status = unsafe { mpv_hook_add(self.ctx, reply_userdata, c_name.as_ptr(), priority) };
+        MpvError::check(status).map_err(error)
+    }
+
+    /// Respond to a MPV_EVENT_HOOK event with the event's `id`, which lets the
+    /// player continue. Must be called exactly once for every hook event, on
+    /// the handle that received it.
+    pub fn hook_continue(&mut self, id: u64) -> Result<(), Error> {
+        let status = unsafe { mpv_hook_continue(self.ctx, id) };
+        MpvError::check(status).map_err(|err| Error::new(Operation::HookContinue, err))
+    }
+
+    /// Interrupt the current mpv_wait_event() call. This will wake up the thread
+    /// currently waiting in mpv_wait_event(). If no thread is waiting, the next
+    /// mpv_wait_event() call will return immediately (this is to avoid lost
+    /// wakeups).
+    ///
+    /// mpv_wait_event() will receive a MPV_EVENT_NONE if it's woken up due to
+    /// this call. But note that this dummy event might be skipped if there are
+    /// already other events queued. All what counts is that the waiting thread
+    /// is woken up at all.
+    ///
+    /// Safe to be called from mpv render API threads.
+    pub fn wakeup(&self) {
+        unsafe { mpv_wakeup(self.ctx) };
+    }
+
+    /// Return the raw mpv_handle, for use with the `raw` bindings (e.g. the
+    /// render API). The handle is still owned by this object, so it must not be
+    /// destroyed, and must not be used after this object is dropped.
+    pub fn as_raw(&self) -> *mut mpv_handle {
+        self.ctx
     }
 
     /// Wait for the next event, or until the timeout expires, or if another thread
@@ -418,30 +841,206 @@ impl MpvHandle {
     ///         the struct, and all memory referenced by it will be automatically
     ///         released by the API on the next mpv_wait_event() call, or when the
     ///         context is destroyed. The return value is never NULL.
+    ///
+    /// After `Event::QueueOverflow`, when events were lost, the properties
+    /// observed on this handle are read again, and the following events are a
+    /// `PropertyChange` for each, with the current value.
     pub fn wait_event(&mut self, timeout: f64) -> Option<Event> {
-        let event = unsafe { mpv_wait_event(self.0, timeout) };
-        let res = if event == null_mut() {
-            None
-        } else {
-            Event::from_mpv_event(unsafe { *event })
+        if let Some(event) = self.resync.pop() {
+            return Some(event);
+        }
+        let event = match &self.buffer {
+            Some(_) => self.recv_buffered(timeout)?,
+            None => unsafe { wait_raw_event(self.ctx, timeout) }?,
         };
-        res
+        if event == Event::QueueOverflow {
+            if let Some(buffer) = &self.buffer {
+                buffer.start_resync();
+            }
+            let mut resync = mem::take(&mut self.resync);
+            resync.overflowed(self);
+            self.resync = resync;
+        }
+        Some(event)
+    }
+
+    /// Take the next event from the buffer. Changes of observed properties
+    /// that were buffered before the last resync are older than the values it
+    /// read, and are skipped.
+    fn recv_buffered(&self, timeout: f64) -> Option<Event> {
+        let buffer = self.buffer.as_ref()?;
+        let deadline = deadline(timeout);
+        loop {
+            match buffer.recv(time_left(deadline)) {
+                Ok(Some(Pumped::Event(event, resyncs))) => {
+                    if resyncs < buffer.resyncs() && self.resync.is_observed_change(&event) {
+                        continue;
+                    }
+                    return Some(event);
+                }
+                Ok(_) => return None,
+                // The thread ended after the shutdown event; mpv keeps
+                // returning it after that.
+                Err(_) => return Some(Event::Shutdown),
+            }
+        }
+    }
+
+    /// How often events were lost so far (see `wait_event()`).
+    pub fn overflows(&self) -> u64 {
+        self.resync.overflows()
+    }
+
+    pub(crate) fn event_buffer(&self) -> Option<&Pump> {
+        self.buffer.as_ref()
+    }
+
+    pub(crate) fn set_event_buffer(&mut self, buffer: Pump) {
+        self.buffer = Some(buffer);
     }
 }
 
-fn free_array<T>(ptr: *mut *mut T) {
-    let mut i = 0;
-    while unsafe { *(ptr.add(i)) != null_mut() } {
-        unsafe { free(*(ptr.add(i)) as _) };
-        i += 8;
+/// mpv_wait_event(), with the event converted.
+///
+/// # Safety
+///
+/// `ctx` must be a valid handle, and no other thread may wait for its events.
+pub(crate) unsafe fn wait_raw_event(ctx: *mut mpv_handle, timeout: f64) -> Option<Event> {
+    let event = unsafe { mpv_wait_event(ctx, timeout) };
+    if event == null_mut() {
+        None
+    } else {
+        Event::from_mpv_event(unsafe { *event })
+    }
+}
+
+fn empty_node() -> mpv_node {
+    mpv_node {
+        format: mpv_format_MPV_FORMAT_NONE,
+        u: mpv_node__bindgen_ty_1 { flag: 0 },
+    }
+}
+
+/// A weak client handle owned by a helper like `Navigator` or `OsdOverlay`.
+///
+/// A weak client keeps `terminate()` and dropping the last other handle of a
+/// player waiting until it is destroyed, so the handle it was created from
+/// destroys it first. After that, requests fail with
+/// `MpvError::Uninitialized`, and `wait_event()` returns `Event::Shutdown`,
+/// like a destroyed player.
+pub(crate) struct WeakClient(Arc<WeakClientState>);
+
+pub(crate) struct WeakClientState {
+    /// For `mpv_wakeup()` while another thread waits for events.
+    raw: *mut mpv_handle,
+    closing: AtomicBool,
+    handle: Mutex<Option<MpvHandle>>,
+    /// Command to run before the handle is destroyed.
+    on_close: Mutex<Option<Vec<String>>>,
+}
+
+// SAFETY: `raw` is only passed to mpv_wakeup(), which is thread-safe, while
+// `handle` still owns it. Everything else is `Send` and `Sync`.
+unsafe impl Send for WeakClientState {}
+// SAFETY: see above.
+unsafe impl Sync for WeakClientState {}
+
+impl WeakClient {
+    /// Run `f` on the handle, if it still exists.
+    fn with<T>(
+        &self,
+        operation: Operation,
+        f: impl FnOnce(&mut MpvHandle) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut handle = self.0.handle.lock().unwrap_or_else(PoisonError::into_inner);
+        match handle.as_mut() {
+            Some(handle) => f(handle),
+            None => Err(Error::new(operation, MpvError::Uninitialized)),
+        }
+    }
+
+    /// Run `args` when the handle it was created from destroys this client,
+    /// e.g. to undo changes that outlive clients.
+    pub(crate) fn on_close(&self, args: Option<Vec<String>>) {
+        *self
+            .0
+            .on_close
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = args;
+    }
+
+    pub(crate) fn command_node_async(
+        &mut self,
+        arg: Node,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        let operation = Operation::Command(command_name(&arg));
+        self.with(operation, |handle| {
+            handle.command_node_async(arg, reply_userdata)
+        })
+    }
+}
+
+impl Player for WeakClient {
+    fn command(&mut self, args: Vec<String>, require_result: bool) -> Result<Option<Node>, Error> {
+        let operation = Operation::Command(args.first().cloned().unwrap_or_default());
+        self.with(operation, |handle| handle.command(args, require_result))
+    }
+
+    fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error> {
+        let operation = Operation::Command(command_name(&arg));
+        self.with(operation, |handle| handle.command_node(arg, require_result))
+    }
+
+    fn get_property(&mut self, name: String) -> Result<Node, Error> {
+        let operation = Operation::GetProperty(name.clone());
+        self.with(operation, |handle| handle.get_property(name))
+    }
+
+    fn set_property(&mut self, name: String, node: Node) -> Result<(), Error> {
+        let operation = Operation::SetProperty(name.clone());
+        self.with(operation, |handle| handle.set_property(name, node))
+    }
+
+    fn observe_property(
+        &mut self,
+        name: String,
+        format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        let operation = Operation::ObserveProperty(name.clone());
+        self.with(operation, |handle| {
+            handle.observe_property(name, format, reply_userdata)
+        })
+    }
+
+    fn unobserve_property(&mut self, reply_userdata: u64) -> Result<(), Error> {
+        self.with(Operation::UnobserveProperty, |handle| {
+            handle.unobserve_property(reply_userdata).map(|_| ())
+        })
+    }
+
+    fn wait_event(&mut self, timeout: f64) -> Option<Event> {
+        if self.0.closing.load(Ordering::SeqCst) {
+            return Some(Event::Shutdown);
+        }
+        let mut handle = self.0.handle.lock().unwrap_or_else(PoisonError::into_inner);
+        match handle.as_mut() {
+            Some(handle) => handle.wait_event(timeout),
+            None => Some(Event::Shutdown),
+        }
     }
-    unsafe { free(ptr as _) };
 }
 
 impl Drop for MpvHandle {
     fn drop(&mut self) {
-        unsafe {
-            mpv_destroy(self.0);
+        self.buffer = None;
+        self.destroy_weak_clients();
+        // Null after terminate().
+        if !self.ctx.is_null() {
+            unsafe {
+                mpv_destroy(self.ctx);
+            }
         }
     }
 }
@@ -0,0 +1,241 @@
+use std::{
+    collections::{hash_map, HashMap, VecDeque},
+    fmt,
+    path::Path,
+};
+
+use super::{
+    builder::MpvBuilder,
+    error::{Error, MpvError},
+    event::{EndFileReason, Event},
+    node::{MpvFormat, Node, Property},
+    player::playlist_entry_id,
+};
+
+/// Why encoding failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodeError {
+    /// Creating or configuring the mpv instance failed.
+    Setup(Vec<Error>),
+    /// An API call failed while encoding.
+    Mpv(Error),
+    /// An input could not be read or decoded.
+    Input { path: String, code: MpvError },
+    /// Encoding was stopped before all inputs were done, e.g. by a "stop" or
+    /// "quit" command from another client.
+    Aborted,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Setup(errors) => {
+                write!(f, "failed to set up encoder")?;
+                for (i, err) in errors.iter().enumerate() {
+                    write!(f, "{} {err}", if i == 0 { ":" } else { ";" })?;
+                }
+                Ok(())
+            }
+            Self::Mpv(err) => write!(f, "{err}"),
+            Self::Input { path, code } => write!(f, "failed to encode '{path}': {code}"),
+            Self::Aborted => write!(f, "encoding was aborted"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl From<Error> for EncodeError {
+    fn from(err: Error) -> Self {
+        Self::Mpv(err)
+    }
+}
+
+/// Progress of an encoding, reported while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Progress {
+    /// Index of the input being encoded.
+    pub input: usize,
+    /// Position in the current input, in seconds ("time-pos").
+    pub time_pos: f64,
+    /// Position in the current input, in percent ("percent-pos").
+    pub percent_pos: f64,
+}
+
+const TIME_POS: u64 = 1;
+const PERCENT_POS: u64 = 2;
+
+/// Encodes or transcodes one or more inputs into a single output file, using
+/// mpv's encoding mode ("o", "of", "ovc", "oac" and related options).
+///
+/// Inputs are played one after another into the same output, so several
+/// inputs are concatenated. `start` and `end` apply to every input, which is
+/// useful to cut a clip out of a single file.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    builder: MpvBuilder,
+    inputs: Vec<String>,
+}
+
+impl Encoder {
+    /// Encode into the given file. Unless `format` is set, the container is
+    /// guessed from the file extension.
+    pub fn new(output: impl AsRef<Path>) -> Self {
+        let builder = MpvBuilder::new()
+            .terminal(false)
+            .config(false)
+            .option("o", output.as_ref().to_string_lossy().into_owned());
+        Self {
+            builder,
+            inputs: vec![],
+        }
+    }
+
+    /// Add an input. Inputs are encoded in the order they were added.
+    pub fn input(mut self, path: impl AsRef<Path>) -> Self {
+        self.inputs
+            .push(path.as_ref().to_string_lossy().into_owned());
+        self
+    }
+
+    /// Container format, e.g. "matroska" or "mp4" ("of").
+    pub fn format(self, format: impl Into<String>) -> Self {
+        self.option("of", format.into())
+    }
+
+    /// Video codec, e.g. "libx264" ("ovc").
+    pub fn video_codec(self, codec: impl Into<String>) -> Self {
+        self.option("ovc", codec.into())
+    }
+
+    /// Audio codec, e.g. "aac" ("oac").
+    pub fn audio_codec(self, codec: impl Into<String>) -> Self {
+        self.option("oac", codec.into())
+    }
+
+    /// Video codec options, as a list of "key=value" pairs ("ovcopts").
+    pub fn video_codec_options(self, options: &[&str]) -> Self {
+        self.option("ovcopts", options.join(","))
+    }
+
+    /// Audio codec options, as a list of "key=value" pairs ("oacopts").
+    pub fn audio_codec_options(self, options: &[&str]) -> Self {
+        self.option("oacopts", options.join(","))
+    }
+
+    /// Start encoding every input at the given position, in seconds.
+    pub fn start(self, seconds: f64) -> Self {
+        self.option("start", seconds.to_string())
+    }
+
+    /// Stop encoding every input at the given position, in seconds.
+    pub fn end(self, seconds: f64) -> Self {
+        self.option("end", seconds.to_string())
+    }
+
+    /// Set any other option, e.g. "vf" to filter the video, or "aid" to
+    /// choose the audio track.
+    pub fn option(mut self, name: impl Into<String>, value: impl Into<Node>) -> Self {
+        self.builder = self.builder.option(name, value);
+        self
+    }
+
+    /// Encode all inputs, and block until the output is complete. `progress` is
+    /// called whenever the position in the current input changes.
+    pub fn run(self, mut progress: impl FnMut(&Progress)) -> Result<(), EncodeError> {
+        if self.inputs.is_empty() {
+            return Ok(());
+        }
+        let mut ctx = self.builder.build().map_err(EncodeError::Setup)?;
+        ctx.observe_property("time-pos".into(), MpvFormat::Float64, TIME_POS)?;
+        ctx.observe_property("percent-pos".into(), MpvFormat::Float64, PERCENT_POS)?;
+        // The input of each playlist entry that isn't done yet.
+        let mut entries = HashMap::new();
+        // Inputs whose entry ID "loadfile" didn't return; they are matched to
+        // entries as those start, in order.
+        let mut unknown = VecDeque::new();
+        for (index, input) in self.inputs.iter().enumerate() {
+            let res = ctx.command(
+                vec!["loadfile".into(), input.clone(), "append-play".into()],
+                true,
+            )?;
+            match playlist_entry_id(res) {
+                Some(id) => {
+                    entries.insert(id, index);
+                }
+                None => unknown.push_back(index),
+            }
+        }
+
+        let mut state = Progress::default();
+        let res = loop {
+            let Some(event) = ctx.wait_event(-1.0) else {
+                continue;
+            };
+            match event {
+                Event::PropertyChange {
+                    result:
+                        Ok(Some(Property {
+                            data: Some(Node::Float64(value)),
+                            ..
+                        })),
+                    reply_userdata,
+                } => {
+                    match reply_userdata {
+                        TIME_POS => state.time_pos = value,
+                        PERCENT_POS => state.percent_pos = value,
+                        _ => continue,
+                    }
+                    progress(&state);
+                }
+                Event::StartFile { playlist_entry_id } => {
+                    let index = match entries.entry(playlist_entry_id) {
+                        hash_map::Entry::Occupied(entry) => *entry.get(),
+                        hash_map::Entry::Vacant(entry) => match unknown.pop_front() {
+                            Some(index) => *entry.insert(index),
+                            None => continue,
+                        },
+                    };
+                    state = Progress {
+                        input: index,
+                        ..Default::default()
+                    };
+                }
+                Event::EndFile {
+                    reason,
+                    playlist_entry_id,
+                    playlist_insert_id,
+                    playlist_insert_num_entries,
+                } => {
+                    // Entries added by someone else are none of our business.
+                    let Some(index) = entries.remove(&playlist_entry_id) else {
+                        continue;
+                    };
+                    match reason {
+                        EndFileReason::EOF => {}
+                        // A playlist: the input is done once all of its
+                        // entries are.
+                        EndFileReason::Redirect => {
+                            let ids =
+                                (0..playlist_insert_num_entries).map(|i| playlist_insert_id + i);
+                            entries.extend(ids.map(|id| (id, index)));
+                        }
+                        EndFileReason::Error(code) => {
+                            let path = self.inputs[index].clone();
+                            break Err(EncodeError::Input { path, code });
+                        }
+                        _ => break Err(EncodeError::Aborted),
+                    }
+                    if entries.is_empty() && unknown.is_empty() {
+                        break Ok(());
+                    }
+                }
+                Event::Shutdown => break Err(EncodeError::Aborted),
+                _ => {}
+            }
+        };
+        // The output file is finalized when the player shuts down.
+        ctx.terminate();
+        res
+    }
+}
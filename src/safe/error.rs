@@ -1,8 +1,9 @@
-use crate::raw::*;
+use std::{fmt, path::PathBuf};
 
-use super::util::make_rust_string_const;
+use crate::raw::*;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MpvError {
     /// The event ringbuffer is full. This means the client is choked, and can't
     /// receive any events. This can happen when too many asynchronous requests
@@ -87,32 +88,214 @@ impl MpvError {
         }
     }
 
-    /// Return a string describing the error. For unknown errors, the string
-    /// "unknown error" is returned.
+    /// Same as `from_mpv_error`, but maps success to `Ok(())`, so the status
+    /// can be used with `?`.
+    pub(crate) fn check(status: mpv_error) -> Result<(), Self> {
+        match Self::from_mpv_error(status) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Return a string describing the error, the same as mpv_error_string()
+    /// does.
     pub fn get_error_string(&self) -> String {
-        let error = match self {
-            Self::EventQueueFull => mpv_error_MPV_ERROR_EVENT_QUEUE_FULL,
-            Self::NoMemory => mpv_error_MPV_ERROR_NOMEM,
-            Self::Uninitialized => mpv_error_MPV_ERROR_UNINITIALIZED,
-            Self::InvalidParameter => mpv_error_MPV_ERROR_INVALID_PARAMETER,
-            Self::OptionNotFound => mpv_error_MPV_ERROR_OPTION_NOT_FOUND,
-            Self::OptionFormatUnsupported => mpv_error_MPV_ERROR_OPTION_FORMAT,
-            Self::OptionError => mpv_error_MPV_ERROR_OPTION_ERROR,
-            Self::PropertyNotFound => mpv_error_MPV_ERROR_PROPERTY_NOT_FOUND,
-            Self::PropertyNotSupported => mpv_error_MPV_ERROR_PROPERTY_FORMAT,
-            Self::PropertyUnavailable => mpv_error_MPV_ERROR_PROPERTY_UNAVAILABLE,
-            Self::PropertyError => mpv_error_MPV_ERROR_PROPERTY_ERROR,
-            Self::CommandError => mpv_error_MPV_ERROR_COMMAND,
-            Self::LoadingFailed => mpv_error_MPV_ERROR_LOADING_FAILED,
-            Self::AudioOutputInitFailed => mpv_error_MPV_ERROR_AO_INIT_FAILED,
-            Self::VideoOutputInitFailed => mpv_error_MPV_ERROR_VO_INIT_FAILED,
-            Self::NothingToPlay => mpv_error_MPV_ERROR_NOTHING_TO_PLAY,
-            Self::UnknownFormat => mpv_error_MPV_ERROR_UNKNOWN_FORMAT,
-            Self::Unsupported => mpv_error_MPV_ERROR_UNSUPPORTED,
-            Self::NotImplemented => mpv_error_MPV_ERROR_NOT_IMPLEMENTED,
-            Self::Unspecified => mpv_error_MPV_ERROR_GENERIC,
-        };
-        let raw = unsafe { mpv_error_string(error) };
-        make_rust_string_const(raw).unwrap()
+        self.as_str().into()
+    }
+
+    /// The string returned by mpv_error_string(), without calling into libmpv.
+    pub fn as_str(&self) -> &'static str {
+        ERROR_STRINGS
+            .iter()
+            .find(|(err, _)| err == self)
+            .map_or("unknown error", |(_, s)| s)
+    }
+
+    /// The error with the given string from mpv_error_string(), e.g. from the
+    /// "error" field of an IPC reply.
+    pub fn from_error_string(error: &str) -> Option<Self> {
+        ERROR_STRINGS
+            .iter()
+            .find(|(_, s)| *s == error)
+            .map(|(err, _)| *err)
+    }
+}
+
+/// The strings mpv_error_string() returns, which IPC replies carry too.
+const ERROR_STRINGS: &[(MpvError, &str)] = &[
+    (MpvError::EventQueueFull, "event queue full"),
+    (MpvError::NoMemory, "memory allocation failed"),
+    (MpvError::Uninitialized, "core not uninitialized"),
+    (MpvError::InvalidParameter, "invalid parameter"),
+    (MpvError::OptionNotFound, "option not found"),
+    (
+        MpvError::OptionFormatUnsupported,
+        "unsupported format for accessing option",
+    ),
+    (MpvError::OptionError, "error setting option"),
+    (MpvError::PropertyNotFound, "property not found"),
+    (
+        MpvError::PropertyNotSupported,
+        "unsupported format for accessing property",
+    ),
+    (MpvError::PropertyUnavailable, "property unavailable"),
+    (MpvError::PropertyError, "error accessing property"),
+    (MpvError::CommandError, "error running command"),
+    (MpvError::LoadingFailed, "loading failed"),
+    (
+        MpvError::AudioOutputInitFailed,
+        "audio output initialization failed",
+    ),
+    (
+        MpvError::VideoOutputInitFailed,
+        "video output initialization failed",
+    ),
+    (MpvError::NothingToPlay, "no audio or video data played"),
+    (MpvError::UnknownFormat, "unrecognized file format"),
+    (MpvError::Unsupported, "not supported"),
+    (MpvError::NotImplemented, "operation not implemented"),
+    (MpvError::Unspecified, "something happened"),
+];
+
+impl fmt::Display for MpvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for MpvError {}
+
+/// The API call that produced an [`Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// mpv_create()
+    Create,
+    /// mpv_initialize()
+    Initialize,
+    /// mpv_load_config_file() with the given file.
+    LoadConfigFile(PathBuf),
+    /// mpv_set_option() with the given option name.
+    SetOption(String),
+    /// mpv_command() and similar, with the given command name.
+    Command(String),
+    /// mpv_get_property() and similar, with the given property name.
+    GetProperty(String),
+    /// mpv_set_property() and similar, with the given property name.
+    SetProperty(String),
+    /// mpv_observe_property() with the given property name.
+    ObserveProperty(String),
+    /// mpv_unobserve_property()
+    UnobserveProperty,
+    /// mpv_request_log_messages() with the given log level.
+    RequestLogMessages(String),
+    /// mpv_hook_add() with the given hook name.
+    HookAdd(String),
+    /// mpv_hook_continue()
+    HookContinue,
+    /// Waiting for an event with mpv_wait_event().
+    WaitEvent,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create => write!(f, "creating mpv instance"),
+            Self::Initialize => write!(f, "initializing mpv"),
+            Self::LoadConfigFile(path) => write!(f, "loading config file {}", path.display()),
+            Self::SetOption(name) => write!(f, "setting option '{name}'"),
+            Self::Command(name) => write!(f, "running command '{name}'"),
+            Self::GetProperty(name) => write!(f, "getting property '{name}'"),
+            Self::SetProperty(name) => write!(f, "setting property '{name}'"),
+            Self::ObserveProperty(name) => write!(f, "observing property '{name}'"),
+            Self::UnobserveProperty => write!(f, "unobserving property"),
+            Self::RequestLogMessages(level) => write!(f, "requesting log messages '{level}'"),
+            Self::HookAdd(name) => write!(f, "adding hook '{name}'"),
+            Self::HookContinue => write!(f, "continuing hook"),
+            Self::WaitEvent => write!(f, "waiting for event"),
+        }
+    }
+}
+
+/// An mpv error code together with the operation that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// What the client was trying to do.
+    pub operation: Operation,
+    /// The error code returned by mpv.
+    pub code: MpvError,
+}
+
+impl Error {
+    pub fn new(operation: Operation, code: MpvError) -> Self {
+        Self { operation, code }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error {}: {}", self.operation, self.code)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.code)
+    }
+}
+
+impl From<Error> for MpvError {
+    fn from(err: Error) -> Self {
+        err.code
+    }
+}
+
+/// The error of a wait for an event, which can also run out of time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitError {
+    /// mpv reported an error, or the awaited event will no longer come.
+    Mpv(Error),
+    /// The event didn't arrive in time, during the given operation.
+    Timeout(Operation),
+}
+
+impl WaitError {
+    /// What the client was waiting for.
+    pub fn operation(&self) -> &Operation {
+        match self {
+            Self::Mpv(err) => &err.operation,
+            Self::Timeout(operation) => operation,
+        }
+    }
+
+    /// The same error, reported for `operation`.
+    pub(crate) fn during(self, operation: Operation) -> Self {
+        match self {
+            Self::Mpv(err) => Self::Mpv(Error::new(operation, err.code)),
+            Self::Timeout(_) => Self::Timeout(operation),
+        }
+    }
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mpv(err) => write!(f, "{err}"),
+            Self::Timeout(operation) => write!(f, "timed out {operation}"),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Mpv(err) => Some(err),
+            Self::Timeout(_) => None,
+        }
+    }
+}
+
+impl From<Error> for WaitError {
+    fn from(err: Error) -> Self {
+        Self::Mpv(err)
     }
 }
@@ -1,9 +1,4 @@
-use std::{
-    ffi::{CStr, CString},
-    ptr::null_mut,
-};
-
-use libc::free;
+use std::ptr::null_mut;
 
 use crate::raw::*;
 
@@ -13,7 +8,8 @@ use super::{
     util::make_rust_string_const,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EndFileReason {
     EOF,
     Stop,
@@ -42,7 +38,8 @@ impl EndFileReason {
 /// MPV_LOG_LEVEL_NONE is never used when receiving messages. The string in
 /// the comment after the value is the name of the log level as used for the
 /// mpv_request_log_messages() function.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LogLevel {
     None,
     Fatal,
@@ -70,7 +67,8 @@ impl LogLevel {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event {
     /// Happens when the player quits. The player enters a state where it tries
     /// to disconnect all clients. Most requests to the player will fail, and
@@ -332,7 +330,6 @@ impl Event {
                 for i in 0..num_args as usize {
                     args.push(make_rust_string_const(unsafe { *(arr.add(i)) }).unwrap());
                 }
-                unsafe { mpv_free(arr as *mut _) };
                 Some(Self::ClientMessage { args })
             }
             mpv_event_id_MPV_EVENT_VIDEO_RECONFIG => Some(Self::VideoReconfig),
@@ -369,39 +366,36 @@ impl Event {
             }
             _ => None,
         };
-        if data != null_mut() {
-            unsafe {
-                mpv_free(data);
-            }
-        }
+        // The event data is owned by mpv, and released on the next
+        // mpv_wait_event() call, so everything above is copied out of it.
         res
     }
 
     pub fn get_event_string(&self) -> String {
-        let event = match self {
-            Event::Shutdown => mpv_event_id_MPV_EVENT_SHUTDOWN,
-            Event::LogMessage { .. } => mpv_event_id_MPV_EVENT_LOG_MESSAGE,
-            Event::GetPropertyReply { .. } => mpv_event_id_MPV_EVENT_GET_PROPERTY_REPLY,
-            Event::SetPropertyReply { .. } => mpv_event_id_MPV_EVENT_SET_PROPERTY_REPLY,
-            Event::CommandReply { .. } => mpv_event_id_MPV_EVENT_COMMAND_REPLY,
-            Event::StartFile { .. } => mpv_event_id_MPV_EVENT_START_FILE,
-            Event::EndFile { .. } => mpv_event_id_MPV_EVENT_END_FILE,
-            Event::FileLoaded => mpv_event_id_MPV_EVENT_FILE_LOADED,
-            Event::Idle => mpv_event_id_MPV_EVENT_IDLE,
-            Event::Tick => mpv_event_id_MPV_EVENT_TICK,
-            Event::ClientMessage { .. } => mpv_event_id_MPV_EVENT_CLIENT_MESSAGE,
-            Event::VideoReconfig => mpv_event_id_MPV_EVENT_VIDEO_RECONFIG,
-            Event::AudioReconfig => mpv_event_id_MPV_EVENT_AUDIO_RECONFIG,
-            Event::Seek => mpv_event_id_MPV_EVENT_SEEK,
-            Event::PlaybackRestart => mpv_event_id_MPV_EVENT_PLAYBACK_RESTART,
-            Event::PropertyChange { .. } => mpv_event_id_MPV_EVENT_PROPERTY_CHANGE,
-            Event::QueueOverflow => mpv_event_id_MPV_EVENT_QUEUE_OVERFLOW,
-            Event::Hook { .. } => mpv_event_id_MPV_EVENT_HOOK,
-        };
-        let s = unsafe { mpv_event_name(event) };
-        CString::from(unsafe { CStr::from_ptr(s) })
-            .to_str()
-            .unwrap()
-            .to_owned()
+        self.name().into()
+    }
+
+    /// The name of the event, as returned by mpv_event_name().
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Shutdown => "shutdown",
+            Event::LogMessage { .. } => "log-message",
+            Event::GetPropertyReply { .. } => "get-property-reply",
+            Event::SetPropertyReply { .. } => "set-property-reply",
+            Event::CommandReply { .. } => "command-reply",
+            Event::StartFile { .. } => "start-file",
+            Event::EndFile { .. } => "end-file",
+            Event::FileLoaded => "file-loaded",
+            Event::Idle => "idle",
+            Event::Tick => "tick",
+            Event::ClientMessage { .. } => "client-message",
+            Event::VideoReconfig => "video-reconfig",
+            Event::AudioReconfig => "audio-reconfig",
+            Event::Seek => "seek",
+            Event::PlaybackRestart => "playback-restart",
+            Event::PropertyChange { .. } => "property-change",
+            Event::QueueOverflow => "event-queue-overflow",
+            Event::Hook { .. } => "hook",
+        }
     }
 }
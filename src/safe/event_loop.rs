@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    thread,
+};
+
+use super::{
+    client::MpvHandle,
+    error::Error,
+    event::{EndFileReason, Event, LogLevel},
+    node::{Node, Property, PropertyValue},
+};
+
+type Handler = Box<dyn FnMut(&mut MpvHandle) + Send>;
+type EndFileHandler = Box<dyn FnMut(&mut MpvHandle, &EndFileReason) + Send>;
+type LogHandler = Box<dyn FnMut(&mut MpvHandle, LogLevel, &str, &str) + Send>;
+type MessageHandler = Box<dyn FnMut(&mut MpvHandle, &[String]) + Send>;
+type EventHandler = Box<dyn FnMut(&mut MpvHandle, &Event) + Send>;
+type PropertyHandler = Box<dyn FnMut(&mut MpvHandle, Option<&Node>) + Send>;
+
+/// Dispatches the events of a handle to callbacks registered per kind of
+/// event.
+///
+/// The loop owns the handle. Either run it on a thread of its own with
+/// `spawn()`, or call `run_pending()` from an existing loop, e.g. once per
+/// frame or after a wakeup callback.
+///
+/// Callbacks get the handle, so they can send commands. They must be `Send`,
+/// because the loop may run on another thread. The loop picks the
+/// `reply_userdata` of observed properties and hooks itself, so don't observe
+/// properties on its handle directly.
+///
+/// After `Event::QueueOverflow`, when events were lost, the handle reads the
+/// observed properties again (see `MpvHandle::wait_event()`), so their
+/// callbacks are called with the current values.
+pub struct EventLoop {
+    ctx: MpvHandle,
+    next_id: u64,
+    file_loaded: Vec<Handler>,
+    end_file: Vec<EndFileHandler>,
+    log: Vec<LogHandler>,
+    client_message: Vec<MessageHandler>,
+    events: Vec<EventHandler>,
+    properties: HashMap<u64, PropertyHandler>,
+    hooks: HashMap<u64, Handler>,
+    shut_down: bool,
+}
+
+impl fmt::Debug for EventLoop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventLoop")
+            .field("properties", &self.properties.len())
+            .field("hooks", &self.hooks.len())
+            .field("overflows", &self.ctx.overflows())
+            .field("shut_down", &self.shut_down)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventLoop {
+    pub fn new(ctx: MpvHandle) -> Self {
+        Self {
+            ctx,
+            next_id: 1,
+            file_loaded: vec![],
+            end_file: vec![],
+            log: vec![],
+            client_message: vec![],
+            events: vec![],
+            properties: HashMap::new(),
+            hooks: HashMap::new(),
+            shut_down: false,
+        }
+    }
+
+    /// The handle, e.g. to send commands outside of callbacks.
+    pub fn handle(&mut self) -> &mut MpvHandle {
+        &mut self.ctx
+    }
+
+    /// Stop dispatching, and return the handle.
+    pub fn into_handle(self) -> MpvHandle {
+        self.ctx
+    }
+
+    /// How often events were lost so far.
+    pub fn overflows(&self) -> u64 {
+        self.ctx.overflows()
+    }
+
+    /// Whether `Event::Shutdown` was dispatched.
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    /// Call `f` when a file was loaded.
+    pub fn on_file_loaded(&mut self, f: impl FnMut(&mut MpvHandle) + Send + 'static) {
+        self.file_loaded.push(Box::new(f));
+    }
+
+    /// Call `f` when a file ended, with the reason.
+    pub fn on_end_file(&mut self, f: impl FnMut(&mut MpvHandle, &EndFileReason) + Send + 'static) {
+        self.end_file.push(Box::new(f));
+    }
+
+    /// Call `f` with every log message at `min_level` (as in
+    /// `request_log_messages()`) or more important, with its level, prefix and
+    /// text. The level applies to all log callbacks.
+    pub fn on_log(
+        &mut self,
+        min_level: &str,
+        f: impl FnMut(&mut MpvHandle, LogLevel, &str, &str) + Send + 'static,
+    ) -> Result<(), Error> {
+        self.ctx.request_log_messages(min_level)?;
+        self.log.push(Box::new(f));
+        Ok(())
+    }
+
+    /// Call `f` with the arguments of every client message, e.g. from
+    /// "script-message".
+    pub fn on_client_message(&mut self, f: impl FnMut(&mut MpvHandle, &[String]) + Send + 'static) {
+        self.client_message.push(Box::new(f));
+    }
+
+    /// Call `f` with every event, before the more specific callbacks.
+    pub fn on_event(&mut self, f: impl FnMut(&mut MpvHandle, &Event) + Send + 'static) {
+        self.events.push(Box::new(f));
+    }
+
+    /// Observe a property, and call `f` with its value converted to `T`
+    /// whenever it changes, once right away. The value is `None` if the
+    /// property is unavailable.
+    ///
+    /// Returns an ID for `remove_property()`.
+    pub fn on_property<T: PropertyValue>(
+        &mut self,
+        name: &str,
+        mut f: impl FnMut(&mut MpvHandle, Option<T>) + Send + 'static,
+    ) -> Result<u64, Error> {
+        let id = self.next_id();
+        self.ctx.observe_property(name.into(), T::FORMAT, id)?;
+        self.properties.insert(
+            id,
+            Box::new(move |ctx, node| f(ctx, node.and_then(T::from_node))),
+        );
+        Ok(id)
+    }
+
+    /// Stop observing a property observed with `on_property()`.
+    pub fn remove_property(&mut self, id: u64) -> Result<(), Error> {
+        if self.properties.remove(&id).is_some() {
+            self.ctx.unobserve_property(id)?;
+        }
+        Ok(())
+    }
+
+    /// Register a hook handler (see `MpvHandle::hook_add()`), and call `f`
+    /// whenever the hook runs. The player waits until `f` returns; the hook
+    /// is continued after that, also if `f` panics.
+    pub fn on_hook(
+        &mut self,
+        name: &str,
+        priority: i32,
+        f: impl FnMut(&mut MpvHandle) + Send + 'static,
+    ) -> Result<(), Error> {
+        let id = self.next_id();
+        self.ctx.hook_add(name, priority, id)?;
+        self.hooks.insert(id, Box::new(f));
+        Ok(())
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Call the callbacks for `event`.
+    pub fn dispatch(&mut self, event: &Event) {
+        let ctx = &mut self.ctx;
+        for f in &mut self.events {
+            f(ctx, event);
+        }
+        match event {
+            Event::Shutdown => self.shut_down = true,
+            Event::FileLoaded => {
+                for f in &mut self.file_loaded {
+                    f(ctx);
+                }
+            }
+            Event::EndFile { reason, .. } => {
+                for f in &mut self.end_file {
+                    f(ctx, reason);
+                }
+            }
+            Event::LogMessage {
+                prefix,
+                text,
+                log_level,
+                ..
+            } => {
+                for f in &mut self.log {
+                    f(ctx, *log_level, prefix, text);
+                }
+            }
+            Event::ClientMessage { args } => {
+                for f in &mut self.client_message {
+                    f(ctx, args);
+                }
+            }
+            Event::PropertyChange {
+                result,
+                reply_userdata,
+            } => {
+                if let Some(f) = self.properties.get_mut(reply_userdata) {
+                    let node = match result {
+                        Ok(Some(Property { data, .. })) => data.as_ref(),
+                        _ => None,
+                    };
+                    f(ctx, node);
+                }
+            }
+            Event::Hook {
+                id, reply_userdata, ..
+            } => {
+                if let Some(f) = self.hooks.get_mut(reply_userdata) {
+                    // Continue the hook even if `f` panics, or the player
+                    // would stay blocked on it.
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| f(ctx)));
+                    let _ = ctx.hook_continue(*id);
+                    if let Err(payload) = res {
+                        panic::resume_unwind(payload);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Wait for the next event for at most `timeout` seconds (see
+    /// `MpvHandle::wait_event()`), and dispatch it. Returns whether there was
+    /// an event.
+    pub fn run_once(&mut self, timeout: f64) -> bool {
+        match self.ctx.wait_event(timeout) {
+            Some(event) => {
+                self.dispatch(&event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Dispatch all queued events without waiting. Returns `false` once the
+    /// player shut down.
+    pub fn run_pending(&mut self) -> bool {
+        while !self.shut_down && self.run_once(0.0) {}
+        !self.shut_down
+    }
+
+    /// Dispatch events until the player shuts down.
+    pub fn run(&mut self) {
+        while !self.shut_down {
+            self.run_once(-1.0);
+        }
+    }
+
+    /// Run the loop on a new thread until the player shuts down, e.g. after
+    /// the "quit" command. The thread returns the loop.
+    pub fn spawn(mut self) -> thread::JoinHandle<Self> {
+        thread::spawn(move || {
+            self.run();
+            self
+        })
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    iter, mem,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{
+            self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError, TrySendError,
+        },
+        Arc, Mutex,
+    },
+    task::Waker,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::raw::{mpv_handle, mpv_hook_continue, mpv_wakeup};
+
+use super::{
+    client::{wait_raw_event, MpvHandle},
+    error::{Error, MpvError, Operation},
+    event::Event,
+    util::deadline,
+};
+
+/// What the thread of `MpvHandle::spawn_event_thread()` sends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    Event(Event),
+    /// This many events were dropped because the channel was full. Unlike
+    /// `Event::QueueOverflow`, mpv's own queue didn't overflow. Either way,
+    /// read the state you track again.
+    Dropped(u64),
+}
+
+/// A handle used from another thread than the `MpvHandle` owning it.
+pub(crate) struct RawHandle(pub(crate) *mut mpv_handle);
+
+// SAFETY: a pump thread only waits for events on the handle, which its owner
+// no longer does once the pump exists, and the owner joins the thread before
+// destroying the handle. Other threads only call mpv_wakeup() and
+// mpv_hook_continue(), which are thread-safe, while they hold the lock on
+// `Stop::handle`, which is cleared before the handle is destroyed.
+unsafe impl Send for RawHandle {}
+
+/// What a pump thread sends.
+pub(crate) enum Pumped {
+    /// An event, with the number of resyncs started before it was read (see
+    /// `Pump::start_resync()`).
+    Event(Event, u64),
+    Dropped(u64),
+    /// mpv_wait_event() returned without an event, e.g. after mpv_wakeup().
+    Wakeup,
+}
+
+/// What a pump thread does when the channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WhenFull {
+    /// Wait for room. mpv's own queue takes the events meanwhile, until it
+    /// overflows too.
+    Wait,
+    /// Drop the event and count it, unless someone waits for it (see
+    /// `must_deliver()`).
+    Drop,
+}
+
+/// Lets other threads wake a pump thread, to stop it or to continue hooks.
+struct Stop {
+    stop: AtomicBool,
+    /// The thread's handle, until the thread is done with it.
+    handle: Mutex<Option<RawHandle>>,
+}
+
+impl Stop {
+    fn request(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = &*self.handle.lock().unwrap() {
+            unsafe { mpv_wakeup(handle.0) };
+        }
+    }
+}
+
+/// Whether an event must reach the receiver even if the channel is full:
+/// without it, the player or a caller waits forever.
+fn must_deliver(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Hook { .. }
+            | Event::CommandReply { .. }
+            | Event::GetPropertyReply { .. }
+            | Event::SetPropertyReply { .. }
+            | Event::Shutdown
+    )
+}
+
+/// A thread that waits for the events of a handle, and sends them to a
+/// channel. It backs both `MpvHandle::buffer_events()` and
+/// `MpvHandle::spawn_event_thread()`.
+///
+/// Dropping it stops the thread, and waits until it's done.
+pub(crate) struct Pump {
+    rx: Receiver<Pumped>,
+    thread: Option<JoinHandle<()>>,
+    stop: Arc<Stop>,
+    /// Woken after every item sent, for `MpvHandle::next_event()`.
+    waker: Arc<Mutex<Option<Waker>>>,
+    resyncs: Arc<AtomicU64>,
+}
+
+impl Pump {
+    /// Start a thread that calls `next` for events of `ctx` until the
+    /// shutdown event, or until the pump is dropped. `next` is dropped on the
+    /// thread when it ends.
+    pub(crate) fn spawn(
+        ctx: *mut mpv_handle,
+        capacity: usize,
+        when_full: WhenFull,
+        next: impl FnMut(f64) -> Option<Event> + Send + 'static,
+    ) -> Self {
+        let (tx, rx) = mpsc::sync_channel(capacity.max(1));
+        let stop = Arc::new(Stop {
+            stop: AtomicBool::new(false),
+            handle: Mutex::new(Some(RawHandle(ctx))),
+        });
+        let waker = Arc::new(Mutex::new(None::<Waker>));
+        let resyncs = Arc::new(AtomicU64::new(0));
+        let (thread_stop, thread_waker, thread_resyncs) =
+            (stop.clone(), waker.clone(), resyncs.clone());
+        let thread = thread::spawn(move || {
+            let mut next = next;
+            let mut pump = PumpThread {
+                tx,
+                when_full,
+                dropped: 0,
+                waker: thread_waker,
+                resyncs: thread_resyncs,
+            };
+            pump.run(&thread_stop.stop, &mut next);
+            // Don't let other threads use the handle once `next` may have
+            // destroyed it.
+            *thread_stop.handle.lock().unwrap() = None;
+            drop(next);
+        });
+        Self {
+            rx,
+            thread: Some(thread),
+            stop,
+            waker,
+            resyncs,
+        }
+    }
+
+    /// Note that the receiver is about to read the observed properties
+    /// again. Property changes read by the thread before are older.
+    pub(crate) fn start_resync(&self) {
+        self.resyncs.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The number of resyncs started so far.
+    pub(crate) fn resyncs(&self) -> u64 {
+        self.resyncs.load(Ordering::SeqCst)
+    }
+
+    /// Wait for the next item, for at most `timeout` seconds (see
+    /// `MpvHandle::wait_event()`). `None` on timeout.
+    pub(crate) fn recv(&self, timeout: f64) -> Result<Option<Pumped>, RecvError> {
+        let item = match deadline(timeout) {
+            Some(deadline) => self
+                .rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => self.rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match item {
+            Ok(item) => Ok(Some(item)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(RecvError),
+        }
+    }
+
+    /// Wake `waker` once an item was sent.
+    pub(crate) fn set_waker(&self, waker: Waker) {
+        *self.waker.lock().unwrap() = Some(waker);
+    }
+
+    /// mpv_hook_continue() on the thread's handle.
+    fn hook_continue(&self, id: u64) -> Result<(), Error> {
+        let error = |err| Error::new(Operation::HookContinue, err);
+        let handle = self.stop.handle.lock().unwrap();
+        let Some(handle) = &*handle else {
+            return Err(error(MpvError::Uninitialized));
+        };
+        MpvError::check(unsafe { mpv_hook_continue(handle.0, id) }).map_err(error)
+    }
+
+    fn join(&mut self) -> thread::Result<()> {
+        self.stop.request();
+        // Disconnect the channel, in case the thread waits for room.
+        let (_, rx) = mpsc::sync_channel(0);
+        drop(mem::replace(&mut self.rx, rx));
+        match self.thread.take() {
+            Some(thread) => thread.join(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Pump {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+/// The sending side of a `Pump`.
+struct PumpThread {
+    tx: SyncSender<Pumped>,
+    when_full: WhenFull,
+    /// Events dropped since the last `Pumped::Dropped`.
+    dropped: u64,
+    waker: Arc<Mutex<Option<Waker>>>,
+    resyncs: Arc<AtomicU64>,
+}
+
+impl PumpThread {
+    fn run(&mut self, stop: &AtomicBool, next: &mut impl FnMut(f64) -> Option<Event>) {
+        while !stop.load(Ordering::SeqCst) {
+            // Retry sending the dropped count soon, even if there are no new
+            // events.
+            let timeout = if self.dropped > 0 { 0.05 } else { -1.0 };
+            let event = next(timeout);
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            if self.dropped > 0 {
+                match self.tx.try_send(Pumped::Dropped(self.dropped)) {
+                    Ok(()) => self.dropped = 0,
+                    Err(TrySendError::Full(_)) => {}
+                    Err(TrySendError::Disconnected(_)) => return,
+                }
+            }
+            let shutdown = event == Some(Event::Shutdown);
+            let sent = match event {
+                Some(event) => self.send(event),
+                None if self.when_full == WhenFull::Wait => self.send_wait(Pumped::Wakeup),
+                None => continue,
+            };
+            if !sent || shutdown {
+                return;
+            }
+        }
+    }
+
+    /// Send or drop an event. Returns `false` if the receiver is gone.
+    fn send(&mut self, event: Event) -> bool {
+        if self.when_full == WhenFull::Wait || must_deliver(&event) {
+            // The dropped count goes first, so events stay in order.
+            if self.dropped > 0 && !self.send_wait(Pumped::Dropped(self.dropped)) {
+                return false;
+            }
+            self.dropped = 0;
+            let resyncs = self.resyncs.load(Ordering::SeqCst);
+            return self.send_wait(Pumped::Event(event, resyncs));
+        }
+        if self.dropped > 0 {
+            self.dropped += 1;
+            return true;
+        }
+        let resyncs = self.resyncs.load(Ordering::SeqCst);
+        match self.tx.try_send(Pumped::Event(event, resyncs)) {
+            Ok(()) => self.wake(),
+            Err(TrySendError::Full(_)) => self.dropped = 1,
+            Err(TrySendError::Disconnected(_)) => return false,
+        }
+        true
+    }
+
+    fn send_wait(&mut self, item: Pumped) -> bool {
+        if self.tx.send(item).is_err() {
+            return false;
+        }
+        self.wake();
+        true
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The receiving end of `MpvHandle::spawn_event_thread()`.
+///
+/// Dropping it stops the thread, and waits until it's done, like `join()`.
+pub struct EventThread {
+    pump: Pump,
+}
+
+impl EventThread {
+    /// Wait for the next event. Fails once the thread has ended and all
+    /// events were received.
+    pub fn recv(&self) -> Result<StreamEvent, RecvError> {
+        self.pump.rx.recv().map(stream_event)
+    }
+
+    /// Wait for the next event for at most `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<StreamEvent, RecvTimeoutError> {
+        self.pump.rx.recv_timeout(timeout).map(stream_event)
+    }
+
+    /// Take the next event if there is one.
+    pub fn try_recv(&self) -> Result<StreamEvent, TryRecvError> {
+        self.pump.rx.try_recv().map(stream_event)
+    }
+
+    /// Iterate over the events until the thread has ended.
+    pub fn iter(&self) -> impl Iterator<Item = StreamEvent> + '_ {
+        iter::from_fn(|| self.recv().ok())
+    }
+
+    /// Let the player go on after an `Event::Hook` with this `id`, like
+    /// `MpvHandle::hook_continue()` on the thread's handle. Fails with
+    /// `MpvError::Uninitialized` once the thread has ended.
+    pub fn hook_continue(&self, id: u64) -> Result<(), Error> {
+        self.pump.hook_continue(id)
+    }
+
+    /// Stop the thread, and wait until it's done. Its handle is destroyed
+    /// then. Returns the thread's panic, if it panicked.
+    pub fn join(mut self) -> thread::Result<()> {
+        self.pump.join()
+    }
+}
+
+fn stream_event(item: Pumped) -> StreamEvent {
+    match item {
+        Pumped::Event(event, _) => StreamEvent::Event(event),
+        Pumped::Dropped(count) => StreamEvent::Dropped(count),
+        // Only sent by pumps that wait for room.
+        Pumped::Wakeup => unreachable!("the event thread doesn't send wakeups"),
+    }
+}
+
+impl MpvHandle {
+    /// Wait for events on a new thread, and send them to the returned
+    /// channel, which holds up to `capacity` events.
+    ///
+    /// The thread takes the handle, because only one thread may wait for its
+    /// events. Use a handle of its own, from `create_client()`, and observe
+    /// properties on it before spawning the thread.
+    ///
+    /// If the channel is full, events are dropped rather than blocking, which
+    /// would make the player's event queue overflow. Once there is room again,
+    /// `StreamEvent::Dropped` is sent with the number of lost events: the
+    /// receiver should read the state it tracks again.
+    ///
+    /// Only events that someone waits for are never dropped: `Event::Hook`
+    /// (continue it with `EventThread::hook_continue()`), the replies to
+    /// asynchronous requests, and `Event::Shutdown`. For these, the thread
+    /// waits for room. `Event::Shutdown` is the last event; the thread ends
+    /// after it, or when the receiving side is dropped or joined. Terminating
+    /// the player waits for the thread, so keep reading the channel meanwhile.
+    pub fn spawn_event_thread(mut self, capacity: usize) -> EventThread {
+        let ctx = self.as_raw();
+        let pump = Pump::spawn(ctx, capacity, WhenFull::Drop, move |timeout| {
+            self.wait_event(timeout)
+        });
+        EventThread { pump }
+    }
+
+    /// Read events ahead from mpv's queue on a thread, into a buffer of `size`
+    /// events. mpv's queue has a fixed size, so this is the way to hold more
+    /// events, e.g. if they are only read once per frame. `wait_event()` takes
+    /// them from the buffer.
+    ///
+    /// Unlike `spawn_event_thread()`, the handle stays here, and no events are
+    /// dropped: while the buffer is full, the thread waits, and mpv's queue
+    /// fills up instead. The thread runs until the handle is destroyed.
+    /// Buffering can't be turned off again. A buffer of 0 events is treated
+    /// as 1.
+    pub fn buffer_events(&mut self, size: usize) {
+        if self.event_buffer().is_none() {
+            let ctx = RawHandle(self.as_raw());
+            let pump = Pump::spawn(ctx.0, size, WhenFull::Wait, move |timeout| {
+                let ctx = &ctx;
+                unsafe { wait_raw_event(ctx.0, timeout) }
+            });
+            self.set_event_buffer(pump);
+        }
+    }
+}
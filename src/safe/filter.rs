@@ -0,0 +1,230 @@
+use std::fmt;
+
+use super::node::Node;
+#[cfg(feature = "libmpv")]
+use super::{
+    client::MpvHandle,
+    error::{Error, MpvError, Operation},
+};
+
+/// Whether a filter works on video ("vf") or audio ("af").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    Video,
+    Audio,
+}
+
+impl FilterType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Video => "vf",
+            Self::Audio => "af",
+        }
+    }
+}
+
+/// A filter in mpv's `vf`/`af` syntax: `[@label:][!]name[=params]`.
+///
+/// Parameters are escaped when needed, so any value can be passed as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub name: String,
+    /// Label used to refer to the filter later, e.g. to remove or toggle it.
+    pub label: Option<String>,
+    /// A disabled filter stays in the chain, but doesn't filter.
+    pub enabled: bool,
+    /// Parameters in order. Positional parameters have no name.
+    pub params: Vec<(Option<String>, String)>,
+}
+
+impl Filter {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            label: None,
+            enabled: true,
+            params: vec![],
+        }
+    }
+
+    /// A libavfilter graph, e.g. "hflip,eq=contrast=1.2". Note that lavfi
+    /// filters that mpv wraps directly, like "eq", can also be used by name.
+    pub fn lavfi(graph: impl Into<String>) -> Self {
+        let graph: String = graph.into();
+        Self::new("lavfi").param("graph", graph)
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Add a positional parameter.
+    pub fn arg(mut self, value: impl ToString) -> Self {
+        self.params.push((None, value.to_string()));
+        self
+    }
+
+    /// Add a named parameter.
+    pub fn param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.push((Some(name.into()), value.to_string()));
+        self
+    }
+
+    /// Decode an entry of the "vf" or "af" property. Returns `None` if the
+    /// node isn't a map with a name.
+    pub fn from_node(node: &Node) -> Option<Self> {
+        let Node::Map(map) = node else {
+            return None;
+        };
+        let Some(Node::String(name)) = map.get("name") else {
+            return None;
+        };
+        let label = match map.get("label") {
+            Some(Node::String(label)) => Some(label.clone()),
+            _ => None,
+        };
+        let enabled = !matches!(map.get("enabled"), Some(Node::Flag(false)));
+        let mut params = match map.get("params") {
+            Some(Node::Map(params)) => params
+                .iter()
+                .filter_map(|(key, value)| match value {
+                    Node::String(value) => Some((Some(key.clone()), value.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        // The property doesn't keep the order, so make it predictable.
+        params.sort();
+        Some(Self {
+            name: name.clone(),
+            label,
+            enabled,
+            params,
+        })
+    }
+}
+
+/// Quote a value with mpv's `%length%` syntax if it contains characters that
+/// have a meaning in filter strings.
+fn escape(value: &str) -> String {
+    let special = |c: char| ":=,%[]\"'@!\\ ".contains(c);
+    if value.is_empty() || value.contains(special) {
+        format!("%{}%{value}", value.len())
+    } else {
+        value.to_owned()
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "@{label}:")?;
+        }
+        if !self.enabled {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.name)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            write!(f, "{}", if i == 0 { "=" } else { ":" })?;
+            if let Some(key) = key {
+                write!(f, "{key}=")?;
+            }
+            write!(f, "{}", escape(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// A list of filters, applied in order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FilterChain(pub Vec<Filter>);
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, filter: Filter) -> Self {
+        self.0.push(filter);
+        self
+    }
+
+    /// Decode the "vf" or "af" property. Entries that aren't filters are
+    /// skipped.
+    pub fn from_node(node: &Node) -> Option<Self> {
+        let Node::Array(filters) = node else {
+            return None;
+        };
+        Some(Self(filters.iter().filter_map(Filter::from_node).collect()))
+    }
+
+    /// Find a filter by its label.
+    pub fn get(&self, label: &str) -> Option<&Filter> {
+        self.0.iter().find(|f| f.label.as_deref() == Some(label))
+    }
+}
+
+impl fmt::Display for FilterChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, filter) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{filter}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "libmpv")]
+impl MpvHandle {
+    fn filter_command(&mut self, kind: FilterType, op: &str, arg: String) -> Result<(), Error> {
+        self.command(vec![kind.as_str().into(), op.into(), arg], false)
+            .map(|_| ())
+    }
+
+    /// Replace all filters ("vf set").
+    pub fn set_filters(&mut self, kind: FilterType, chain: &FilterChain) -> Result<(), Error> {
+        self.filter_command(kind, "set", chain.to_string())
+    }
+
+    /// Append a filter ("vf add"). A filter with the same label is replaced.
+    pub fn add_filter(&mut self, kind: FilterType, filter: &Filter) -> Result<(), Error> {
+        self.filter_command(kind, "add", filter.to_string())
+    }
+
+    /// Remove a filter ("vf remove"). A filter with a label is found by its
+    /// label, others by name and parameters.
+    pub fn remove_filter(&mut self, kind: FilterType, filter: &Filter) -> Result<(), Error> {
+        self.filter_command(kind, "remove", filter.to_string())
+    }
+
+    /// Add the filter if it isn't in the chain, remove it otherwise ("vf
+    /// toggle"). A labeled filter that is in the chain is enabled or disabled
+    /// instead.
+    pub fn toggle_filter(&mut self, kind: FilterType, filter: &Filter) -> Result<(), Error> {
+        self.filter_command(kind, "toggle", filter.to_string())
+    }
+
+    /// Remove all filters ("vf clr").
+    pub fn clear_filters(&mut self, kind: FilterType) -> Result<(), Error> {
+        self.filter_command(kind, "clr", String::new())
+    }
+
+    /// The current filters.
+    pub fn filters(&mut self, kind: FilterType) -> Result<FilterChain, Error> {
+        let name = kind.as_str();
+        let node = self.get_property(name.into())?;
+        FilterChain::from_node(&node).ok_or(Error::new(
+            Operation::GetProperty(name.into()),
+            MpvError::PropertyError,
+        ))
+    }
+}
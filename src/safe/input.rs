@@ -0,0 +1,282 @@
+use std::{collections::HashMap, fmt};
+
+use super::{
+    client::MpvHandle,
+    error::{Error, MpvError, Operation},
+    event::Event,
+    util::quote_arg,
+};
+
+/// How the bindings of a section relate to the user's key bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionMode {
+    /// Only use a binding if the user hasn't bound the key to a command.
+    #[default]
+    Default,
+    /// Always use the bindings. If several enabled sections bind the same key,
+    /// the one enabled last wins.
+    Force,
+}
+
+impl SectionMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Force => "force",
+        }
+    }
+}
+
+/// Flags for "enable-section".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EnableFlags {
+    /// Hide the mouse cursor on inactivity, even if the section binds mouse
+    /// movement.
+    pub allow_hide_cursor: bool,
+    /// Let the video window be dragged, even if the section binds the left
+    /// mouse button.
+    pub allow_vo_dragging: bool,
+    /// Disable all other sections until this one is disabled.
+    pub exclusive: bool,
+}
+
+impl EnableFlags {
+    fn to_arg(self) -> String {
+        let flags = [
+            (self.allow_hide_cursor, "allow-hide-cursor"),
+            (self.allow_vo_dragging, "allow-vo-dragging"),
+            (self.exclusive, "exclusive"),
+        ];
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, flag)| *flag)
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+}
+
+type Handler = Box<dyn FnMut(&mut MpvHandle)>;
+
+fn invalid_binding() -> Error {
+    Error::new(
+        Operation::Command("define-section".into()),
+        MpvError::InvalidParameter,
+    )
+}
+
+/// Keys are put in the section's input.conf text as they are.
+fn check_key(key: String) -> Result<String, Error> {
+    if key.is_empty() || key.contains([' ', '\t', '\n', '\r', '#']) {
+        return Err(invalid_binding());
+    }
+    Ok(key)
+}
+
+/// A named set of key bindings, defined with "define-section".
+///
+/// Keys can run mpv commands, or Rust closures. A key bound to a closure sends
+/// a client message (`script-message-to <client> <section> <key>`) to the
+/// handle that defined the section, and `handle_event()` calls the closure
+/// when the message arrives.
+///
+/// Keys are named as in input.conf, e.g. "x", "Ctrl+a", "MBTN_LEFT" or
+/// "WHEEL_UP". A section does nothing until it is enabled.
+pub struct InputSection {
+    name: String,
+    mode: SectionMode,
+    /// (key, command), in the order they were bound. `None` for closures.
+    bindings: Vec<(String, Option<String>)>,
+    handlers: HashMap<String, Handler>,
+}
+
+impl fmt::Debug for InputSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputSection")
+            .field("name", &self.name)
+            .field("mode", &self.mode)
+            .field("bindings", &self.bindings)
+            .finish_non_exhaustive()
+    }
+}
+
+impl InputSection {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            mode: SectionMode::Default,
+            bindings: vec![],
+            handlers: HashMap::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn mode(mut self, mode: SectionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Call `handler` when `key` is pressed. Binding a key again replaces
+    /// the previous binding.
+    ///
+    /// Fails with `MpvError::InvalidParameter` if `key` contains a space, a
+    /// newline or '#', which input.conf can't hold; use their names instead
+    /// ("SPACE", "ENTER", "SHARP").
+    pub fn bind(
+        mut self,
+        key: impl Into<String>,
+        handler: impl FnMut(&mut MpvHandle) + 'static,
+    ) -> Result<Self, Error> {
+        let key = check_key(key.into())?;
+        self.set_binding(key.clone(), None);
+        self.handlers.insert(key, Box::new(handler));
+        Ok(self)
+    }
+
+    /// Run an mpv command, in input.conf syntax, when `key` is pressed.
+    ///
+    /// Fails like `bind()`, or if `command` contains a newline.
+    pub fn bind_command(
+        mut self,
+        key: impl Into<String>,
+        command: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let key = check_key(key.into())?;
+        let command = command.into();
+        if command.contains('\n') {
+            return Err(invalid_binding());
+        }
+        self.handlers.remove(&key);
+        self.set_binding(key, Some(command));
+        Ok(self)
+    }
+
+    fn set_binding(&mut self, key: String, command: Option<String>) {
+        match self.bindings.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, c)) => *c = command,
+            None => self.bindings.push((key, command)),
+        }
+    }
+
+    /// Define the section, or replace its bindings if it was defined before.
+    /// Closures are called for messages to `ctx`.
+    pub fn define(&self, ctx: &mut MpvHandle) -> Result<(), Error> {
+        let client = quote_arg(&ctx.name());
+        let section = quote_arg(&self.name);
+        let contents = self
+            .bindings
+            .iter()
+            .map(|(key, command)| match command {
+                Some(command) => format!("{key} {command}\n"),
+                None => format!(
+                    "{key} script-message-to {client} {section} {}\n",
+                    quote_arg(key)
+                ),
+            })
+            .collect::<String>();
+        ctx.command(
+            vec![
+                "define-section".into(),
+                self.name.clone(),
+                contents,
+                self.mode.as_str().into(),
+            ],
+            false,
+        )
+        .map(|_| ())
+    }
+
+    pub fn enable(&self, ctx: &mut MpvHandle, flags: EnableFlags) -> Result<(), Error> {
+        ctx.command(
+            vec!["enable-section".into(), self.name.clone(), flags.to_arg()],
+            false,
+        )
+        .map(|_| ())
+    }
+
+    pub fn disable(&self, ctx: &mut MpvHandle) -> Result<(), Error> {
+        ctx.command(vec!["disable-section".into(), self.name.clone()], false)
+            .map(|_| ())
+    }
+
+    /// Call the closure of a key of this section, if `event` is the message
+    /// for it. Returns whether the event was handled.
+    pub fn handle_event(&mut self, ctx: &mut MpvHandle, event: &Event) -> bool {
+        let Event::ClientMessage { args } = event else {
+            return false;
+        };
+        self.handle_message(ctx, args)
+    }
+
+    /// Same as `handle_event()`, with the arguments of a client message.
+    pub fn handle_message(&mut self, ctx: &mut MpvHandle, args: &[String]) -> bool {
+        match args {
+            [section, key] if *section == self.name => self.press(ctx, key),
+            _ => false,
+        }
+    }
+
+    /// Call the closure bound to `key`. Returns whether there is one.
+    pub(crate) fn press(&mut self, ctx: &mut MpvHandle, key: &str) -> bool {
+        match self.handlers.get_mut(key) {
+            Some(handler) => {
+                handler(ctx);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl MpvHandle {
+    /// Simulate a key press: a key down followed by a key up.
+    pub fn keypress(&mut self, key: &str) -> Result<(), Error> {
+        self.command(vec!["keypress".into(), key.into()], false)
+            .map(|_| ())
+    }
+
+    /// Simulate pressing a key down, without releasing it.
+    pub fn keydown(&mut self, key: &str) -> Result<(), Error> {
+        self.command(vec!["keydown".into(), key.into()], false)
+            .map(|_| ())
+    }
+
+    /// Release a key pressed with `keydown()`, or all keys with `None`.
+    pub fn keyup(&mut self, key: Option<&str>) -> Result<(), Error> {
+        let mut args = vec!["keyup".into()];
+        args.extend(key.map(String::from));
+        self.command(args, false).map(|_| ())
+    }
+
+    /// Bind a key to an mpv command, in input.conf syntax, in the default
+    /// section. This overrides the user's binding for the key.
+    pub fn keybind(&mut self, key: &str, command: &str) -> Result<(), Error> {
+        self.command(vec!["keybind".into(), key.into(), command.into()], false)
+            .map(|_| ())
+    }
+
+    /// Move the mouse to the given position in the video window, in pixels.
+    pub fn mouse_move(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        self.command(vec!["mouse".into(), x.to_string(), y.to_string()], false)
+            .map(|_| ())
+    }
+
+    /// Move the mouse, and click `button` (0 to 19, 0 is the left button).
+    pub fn mouse_click(&mut self, x: i32, y: i32, button: u32, double: bool) -> Result<(), Error> {
+        let mode = if double { "double" } else { "single" };
+        self.command(
+            vec![
+                "mouse".into(),
+                x.to_string(),
+                y.to_string(),
+                button.to_string(),
+                mode.into(),
+            ],
+            false,
+        )
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::safe::{
+    error::{Error, MpvError, Operation},
+    event::Event,
+    node::{MpvFormat, Node, Property},
+    player::Player,
+    util::{command_name, deadline},
+};
+
+use super::json::{error_from_string, json_to_event, json_to_node, node_to_json};
+
+/// Controls a separate mpv process through its JSON IPC socket, as created
+/// with `--input-ipc-server`, or an embedded player served by `IpcServer`.
+///
+/// This doesn't use libmpv. Values and events are converted to the same types
+/// as used by `MpvHandle`, and both implement `Player`. Requests are
+/// synchronous: events that arrive while waiting for a reply are queued, and
+/// returned by `wait_event()`.
+///
+/// A lost connection is reported as `MpvError::Uninitialized` by requests,
+/// and as `Event::Shutdown` by `wait_event()`, like a destroyed player.
+pub struct IpcClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    /// Bytes of a message that was cut off by a read timeout.
+    partial: Vec<u8>,
+    events: VecDeque<Event>,
+    /// Requested format of observed properties, by reply_userdata.
+    observed: HashMap<u64, MpvFormat>,
+    next_request_id: u64,
+    closed: bool,
+}
+
+impl IpcClient {
+    /// Connect to the socket at `path`.
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_stream(UnixStream::connect(path)?)
+    }
+
+    /// Use an already connected socket.
+    pub fn from_stream(stream: UnixStream) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            partial: Vec::new(),
+            events: VecDeque::new(),
+            observed: HashMap::new(),
+            next_request_id: 1,
+            closed: false,
+        })
+    }
+
+    /// Send a raw command, e.g. `["client_name"]`, and wait for the reply.
+    /// Returns the reply's "data" field, which is `null` if there is none.
+    pub fn request(&mut self, command: Value) -> io::Result<Result<Value, MpvError>> {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        let mut line = json!({ "command": command, "request_id": request_id }).to_string();
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        loop {
+            let Some(mut msg) = self.read_message(None)? else {
+                continue;
+            };
+            if msg.get("event").is_some() {
+                self.queue_event(&msg);
+                continue;
+            }
+            if msg.get("request_id").and_then(Value::as_u64) != Some(request_id) {
+                continue;
+            }
+            let error = msg.get("error").and_then(Value::as_str).unwrap_or("");
+            return Ok(error_from_string(error).map(|_| msg["data"].take()));
+        }
+    }
+
+    /// Read one message. `timeout` of `None` waits forever. Returns `None` on
+    /// timeout.
+    fn read_message(&mut self, timeout: Option<Duration>) -> io::Result<Option<Value>> {
+        if self.closed {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        // A zero timeout is rejected by the socket, and means "no timeout".
+        let timeout = timeout.map(|t| t.max(Duration::from_millis(1)));
+        self.reader.get_ref().set_read_timeout(timeout)?;
+        match self.reader.read_until(b'\n', &mut self.partial) {
+            Ok(0) => {
+                self.closed = true;
+                Err(io::ErrorKind::UnexpectedEof.into())
+            }
+            Ok(_) if !self.partial.ends_with(b"\n") => {
+                self.closed = true;
+                Err(io::ErrorKind::UnexpectedEof.into())
+            }
+            Ok(_) => {
+                let line = std::mem::take(&mut self.partial);
+                // mpv never sends invalid JSON; skip anything else.
+                Ok(serde_json::from_slice(&line).ok())
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(err) => {
+                self.closed = true;
+                Err(err)
+            }
+        }
+    }
+
+    fn queue_event(&mut self, msg: &Value) {
+        let Some(mut event) = json_to_event(msg) else {
+            return;
+        };
+        // JSON doesn't keep the type of numbers that happen to be integral,
+        // so restore the format the property was observed with.
+        if let Event::PropertyChange {
+            result: Ok(Some(Property { data, .. })),
+            reply_userdata,
+        } = &mut event
+        {
+            if let (Some(MpvFormat::Float64), Some(Node::Int64(value))) =
+                (self.observed.get(reply_userdata), &data)
+            {
+                *data = Some(Node::Float64(*value as f64));
+            }
+        }
+        self.events.push_back(event);
+    }
+
+    fn node_request(
+        &mut self,
+        operation: Operation,
+        command: Value,
+    ) -> Result<Option<Node>, Error> {
+        match self.request(command) {
+            Ok(Ok(data)) => Ok(json_to_node(&data)),
+            Ok(Err(code)) => Err(Error::new(operation, code)),
+            Err(_) => Err(Error::new(operation, MpvError::Uninitialized)),
+        }
+    }
+}
+
+impl Player for IpcClient {
+    fn command(&mut self, args: Vec<String>, require_result: bool) -> Result<Option<Node>, Error> {
+        let operation = Operation::Command(args.first().cloned().unwrap_or_default());
+        let command = Value::from(args);
+        let res = self.node_request(operation, command)?;
+        Ok(res.filter(|_| require_result))
+    }
+
+    fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error> {
+        let operation = Operation::Command(command_name(&arg));
+        let command = node_to_json(&arg);
+        let res = self.node_request(operation, command)?;
+        Ok(res.filter(|_| require_result))
+    }
+
+    fn get_property(&mut self, name: String) -> Result<Node, Error> {
+        let operation = Operation::GetProperty(name.clone());
+        let res = self.node_request(operation.clone(), json!(["get_property", name]))?;
+        res.ok_or(Error::new(operation, MpvError::PropertyUnavailable))
+    }
+
+    fn set_property(&mut self, name: String, node: Node) -> Result<(), Error> {
+        let command = json!(["set_property", name, node_to_json(&node)]);
+        self.node_request(Operation::SetProperty(name), command)
+            .map(|_| ())
+    }
+
+    fn observe_property(
+        &mut self,
+        name: String,
+        format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        let cmd = match format {
+            MpvFormat::String | MpvFormat::OsdString => "observe_property_string",
+            _ => "observe_property",
+        };
+        let command = json!([cmd, reply_userdata, name]);
+        self.node_request(Operation::ObserveProperty(name), command)?;
+        self.observed.insert(reply_userdata, format);
+        Ok(())
+    }
+
+    fn unobserve_property(&mut self, reply_userdata: u64) -> Result<(), Error> {
+        self.observed.remove(&reply_userdata);
+        let command = json!(["unobserve_property", reply_userdata]);
+        self.node_request(Operation::UnobserveProperty, command)
+            .map(|_| ())
+    }
+
+    fn wait_event(&mut self, timeout: f64) -> Option<Event> {
+        let deadline = deadline(timeout);
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            match self.read_message(remaining) {
+                Ok(Some(msg)) => self.queue_event(&msg),
+                Ok(None) if remaining.is_some_and(|r| r.is_zero()) => return None,
+                Ok(None) => {}
+                Err(_) => return Some(Event::Shutdown),
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Conversions between the safe types and mpv's JSON IPC representation.
+
+use serde_json::{Map, Number, Value};
+
+use crate::safe::{
+    error::MpvError,
+    event::{EndFileReason, Event, LogLevel},
+    node::{Node, Property},
+};
+
+/// Convert a node to JSON. Byte arrays can't be represented in mpv's JSON, and
+/// become `null`, as do non-finite numbers.
+pub fn node_to_json(node: &Node) -> Value {
+    match node {
+        Node::String(s) | Node::OsdString(s) => Value::String(s.clone()),
+        Node::Flag(flag) => Value::Bool(*flag),
+        Node::Int64(int) => Value::Number((*int).into()),
+        Node::Float64(float) => Number::from_f64(*float).map_or(Value::Null, Value::Number),
+        Node::Array(arr) => Value::Array(arr.iter().map(node_to_json).collect()),
+        Node::ByteArray(_) => Value::Null,
+        Node::Map(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), node_to_json(value)))
+                .collect(),
+        ),
+        Node::Node(node) => node_to_json(node),
+    }
+}
+
+/// Convert JSON to a node. `null` has no node equivalent, and returns `None`;
+/// inside arrays and objects, such entries are dropped.
+pub fn json_to_node(value: &Value) -> Option<Node> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(flag) => Node::Flag(*flag),
+        Value::Number(num) => match num.as_i64() {
+            Some(int) => Node::Int64(int),
+            None => Node::Float64(num.as_f64()?),
+        },
+        Value::String(s) => Node::String(s.clone()),
+        Value::Array(arr) => Node::Array(arr.iter().filter_map(json_to_node).collect()),
+        Value::Object(map) => Node::Map(
+            map.iter()
+                .filter_map(|(key, value)| Some((key.clone(), json_to_node(value)?)))
+                .collect(),
+        ),
+    })
+}
+
+/// The name of an end file reason, as used in the "reason" field of
+/// "end-file" events.
+pub fn end_file_reason_name(reason: &EndFileReason) -> &'static str {
+    match reason {
+        EndFileReason::EOF => "eof",
+        EndFileReason::Stop => "stop",
+        EndFileReason::Quit => "quit",
+        EndFileReason::Error(_) => "error",
+        EndFileReason::Redirect => "redirect",
+    }
+}
+
+/// Convert an event into the message mpv sends to IPC clients. Replies to
+/// asynchronous requests are not events in the protocol, and return `None`.
+pub fn event_to_json(event: &Event) -> Option<Value> {
+    let mut msg = Map::new();
+    msg.insert("event".into(), event.name().into());
+    match event {
+        Event::GetPropertyReply { .. }
+        | Event::SetPropertyReply { .. }
+        | Event::CommandReply { .. } => return None,
+        Event::LogMessage {
+            prefix,
+            level,
+            text,
+            ..
+        } => {
+            msg.insert("prefix".into(), prefix.as_str().into());
+            msg.insert("level".into(), level.as_str().into());
+            msg.insert("text".into(), text.as_str().into());
+        }
+        Event::StartFile { playlist_entry_id } => {
+            msg.insert("playlist_entry_id".into(), (*playlist_entry_id).into());
+        }
+        Event::EndFile {
+            reason,
+            playlist_entry_id,
+            playlist_insert_id,
+            playlist_insert_num_entries,
+        } => {
+            msg.insert("reason".into(), end_file_reason_name(reason).into());
+            msg.insert("playlist_entry_id".into(), (*playlist_entry_id).into());
+            if let EndFileReason::Error(err) = reason {
+                msg.insert("file_error".into(), err.to_string().into());
+            }
+            if *playlist_insert_id != 0 {
+                msg.insert("playlist_insert_id".into(), (*playlist_insert_id).into());
+                msg.insert(
+                    "playlist_insert_num_entries".into(),
+                    (*playlist_insert_num_entries).into(),
+                );
+            }
+        }
+        Event::ClientMessage { args } => {
+            msg.insert("args".into(), args.clone().into());
+        }
+        Event::PropertyChange {
+            result,
+            reply_userdata,
+        } => {
+            msg.insert("id".into(), (*reply_userdata).into());
+            if let Ok(Some(property)) = result {
+                msg.insert("name".into(), property.name.as_str().into());
+                if let Some(data) = &property.data {
+                    msg.insert("data".into(), node_to_json(data));
+                }
+            }
+        }
+        Event::Hook { id, .. } => {
+            msg.insert("hook_id".into(), (*id).into());
+        }
+        _ => {}
+    }
+    Some(Value::Object(msg))
+}
+
+/// Parse the "error" field of a reply. Returns `Ok` for "success"; unknown
+/// strings are `MpvError::Unspecified`.
+pub fn error_from_string(error: &str) -> Result<(), MpvError> {
+    if error == "success" {
+        return Ok(());
+    }
+    Err(MpvError::from_error_string(error).unwrap_or(MpvError::Unspecified))
+}
+
+fn log_level_from_string(level: &str) -> LogLevel {
+    match level {
+        "fatal" => LogLevel::Fatal,
+        "error" => LogLevel::Error,
+        "warn" => LogLevel::Warn,
+        "info" => LogLevel::Info,
+        "v" => LogLevel::Noise,
+        "debug" => LogLevel::Debug,
+        "trace" => LogLevel::Trace,
+        _ => LogLevel::None,
+    }
+}
+
+/// Convert an event message sent by mpv to IPC clients back into an event.
+/// Returns `None` for messages that are not events, or unknown events.
+pub fn json_to_event(msg: &Value) -> Option<Event> {
+    let str_field = |key: &str| msg.get(key).and_then(Value::as_str).unwrap_or("");
+    let int_field = |key: &str| msg.get(key).and_then(Value::as_i64).unwrap_or(0);
+    Some(match msg.get("event")?.as_str()? {
+        "shutdown" => Event::Shutdown,
+        "log-message" => Event::LogMessage {
+            prefix: str_field("prefix").into(),
+            level: str_field("level").into(),
+            text: str_field("text").into(),
+            log_level: log_level_from_string(str_field("level")),
+        },
+        "start-file" => Event::StartFile {
+            playlist_entry_id: int_field("playlist_entry_id"),
+        },
+        "end-file" => Event::EndFile {
+            reason: match str_field("reason") {
+                "eof" => EndFileReason::EOF,
+                "stop" => EndFileReason::Stop,
+                "quit" => EndFileReason::Quit,
+                "redirect" => EndFileReason::Redirect,
+                "error" => EndFileReason::Error(error_from_string(str_field("file_error")).err()?),
+                _ => return None,
+            },
+            playlist_entry_id: int_field("playlist_entry_id"),
+            playlist_insert_id: int_field("playlist_insert_id"),
+            playlist_insert_num_entries: int_field("playlist_insert_num_entries"),
+        },
+        "file-loaded" => Event::FileLoaded,
+        "idle" => Event::Idle,
+        "tick" => Event::Tick,
+        "client-message" => Event::ClientMessage {
+            args: msg
+                .get("args")?
+                .as_array()?
+                .iter()
+                .filter_map(|arg| Some(arg.as_str()?.to_owned()))
+                .collect(),
+        },
+        "video-reconfig" => Event::VideoReconfig,
+        "audio-reconfig" => Event::AudioReconfig,
+        "seek" => Event::Seek,
+        "playback-restart" => Event::PlaybackRestart,
+        "property-change" => Event::PropertyChange {
+            result: Ok(Some(Property {
+                name: str_field("name").into(),
+                data: msg.get("data").and_then(json_to_node),
+            })),
+            reply_userdata: msg.get("id").and_then(Value::as_u64).unwrap_or(0),
+        },
+        "event-queue-overflow" => Event::QueueOverflow,
+        "hook" => Event::Hook {
+            name: String::new(),
+            id: msg.get("hook_id").and_then(Value::as_u64).unwrap_or(0),
+            reply_userdata: 0,
+        },
+        _ => return None,
+    })
+}
@@ -0,0 +1,13 @@
+//! mpv's JSON IPC protocol, as used by `--input-ipc-server`.
+//!
+//! Requires the `ipc` feature. The server also requires the `libmpv` feature;
+//! the client doesn't.
+
+pub mod client;
+pub mod json;
+#[cfg(feature = "libmpv")]
+pub mod server;
+
+pub use client::*;
+#[cfg(feature = "libmpv")]
+pub use server::*;
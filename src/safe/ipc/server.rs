@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    io::{BufRead, BufReader, Write},
+    net::Shutdown,
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    raw::*,
+    safe::{
+        client::{client_api_version, MpvHandle},
+        error::MpvError,
+        event::Event,
+        node::{MpvFormat, Node},
+        util::make_rust_string_const,
+    },
+};
+
+use super::json::{event_to_json, json_to_node, node_to_json};
+
+/// Serves an embedded player over a Unix domain socket, speaking the same JSON
+/// protocol as mpv's `--input-ipc-server`, so tools written for mpv (socat,
+/// mpvc, remote control scripts) can drive it.
+///
+/// Like in mpv, every connection gets its own client handle, and with it its
+/// own observed properties and event stream. Lines that are not JSON are run
+/// as input.conf commands, without reply.
+///
+/// The server stops accepting connections when it is dropped, or when the
+/// player shuts down. Open connections are closed then as well, or earlier by
+/// the peer; dropping the server waits until their threads have ended.
+pub struct IpcServer {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IpcServer {
+    /// Listen on the socket at `path`. A stale socket at that path is replaced;
+    /// any other existing file is an error.
+    pub fn start(ctx: &mut MpvHandle, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Ok(meta) = fs::symlink_metadata(&path) {
+            if meta.file_type().is_socket() {
+                fs::remove_file(&path)?;
+            }
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        let Some(server) = ctx.create_weak_client(c"ipc-server") else {
+            return Err(io::Error::other("failed to create mpv client"));
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || listen(server, listener, &stop))
+        };
+        Ok(Self {
+            path,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn listen(mut server: MpvHandle, listener: UnixListener, stop: &AtomicBool) {
+    // Each connection with a clone of its stream, to close it on shutdown.
+    let mut connections: Vec<(UnixStream, JoinHandle<()>)> = vec![];
+    while !stop.load(Ordering::Relaxed) {
+        connections.retain(|(_, thread)| !thread.is_finished());
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if stream.set_nonblocking(false).is_err() {
+                    continue;
+                }
+                let Ok(closer) = stream.try_clone() else {
+                    continue;
+                };
+                if let Some(client) = server.create_client(c"ipc") {
+                    let thread = thread::spawn(move || Connection::new(client).serve(stream));
+                    connections.push((closer, thread));
+                }
+            }
+            // The listener is polled, and the handle's event queue doubles as
+            // the sleep, so a shutdown of the player is noticed. A handle
+            // left alive here would block mpv_terminate_destroy() forever.
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                if let Some(Event::Shutdown) = server.wait_event(0.1) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    // Closing the socket ends the connection's reader, and with it the
+    // connection.
+    for (stream, thread) in connections {
+        let _ = stream.shutdown(Shutdown::Both);
+        let _ = thread.join();
+    }
+}
+
+/// Wakes up a handle's event loop from the socket reader thread.
+struct Waker(*mut mpv_handle);
+
+// mpv_wakeup() may be called from any thread. The reader thread is scoped, so
+// the handle outlives it.
+unsafe impl Send for Waker {}
+
+impl Waker {
+    fn wake(&self) {
+        unsafe { mpv_wakeup(self.0) };
+    }
+}
+
+struct Connection {
+    ctx: MpvHandle,
+    /// Request IDs of pending async commands, by reply_userdata.
+    pending: HashMap<u64, Value>,
+    next_async_id: u64,
+}
+
+impl Connection {
+    fn new(ctx: MpvHandle) -> Self {
+        Self {
+            ctx,
+            pending: HashMap::new(),
+            next_async_id: 1,
+        }
+    }
+
+    fn serve(mut self, stream: UnixStream) {
+        let Ok(reader) = stream.try_clone() else {
+            return;
+        };
+        let mut writer = stream;
+        let (tx, rx) = mpsc::channel::<String>();
+        let waker = Waker(self.ctx.as_raw());
+
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                for line in BufReader::new(reader).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                    waker.wake();
+                }
+                // Dropping the sender tells the event loop the peer is gone.
+                drop(tx);
+                waker.wake();
+            });
+
+            'serve: loop {
+                loop {
+                    match rx.try_recv() {
+                        Ok(line) => {
+                            if let Some(reply) = self.handle_line(&line) {
+                                if send(&mut writer, &reply).is_err() {
+                                    break 'serve;
+                                }
+                            }
+                        }
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        Err(mpsc::TryRecvError::Disconnected) => break 'serve,
+                    }
+                }
+                let Some(event) = self.ctx.wait_event(-1.0) else {
+                    continue;
+                };
+                let msg = match &event {
+                    Event::CommandReply {
+                        result,
+                        reply_userdata,
+                    } => match self.pending.remove(reply_userdata) {
+                        Some(request_id) => reply(request_id, result.clone()),
+                        None => continue,
+                    },
+                    event => match event_to_json(event) {
+                        Some(msg) => msg,
+                        None => continue,
+                    },
+                };
+                if send(&mut writer, &msg).is_err() || event == Event::Shutdown {
+                    break;
+                }
+            }
+            // Unblock the reader thread, so the scope can end.
+            let _ = writer.shutdown(std::net::Shutdown::Both);
+        });
+    }
+
+    /// Handle one line from the client, and return the reply, if any.
+    fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if !line.starts_with('{') {
+            let _ = self.ctx.command_string(line);
+            return None;
+        }
+        let Ok(Value::Object(mut request)) = serde_json::from_str::<Value>(line) else {
+            return Some(reply(Value::from(0), Err(MpvError::InvalidParameter)));
+        };
+        let request_id = request.remove("request_id").unwrap_or(Value::from(0));
+        let is_async = request.get("async") == Some(&Value::Bool(true));
+        let res = match request.remove("command") {
+            Some(Value::Array(args)) => match self.special_command(&args) {
+                Some(res) => res,
+                None => return self.command(request_id, &Value::Array(args), is_async),
+            },
+            Some(args @ Value::Object(_)) => return self.command(request_id, &args, is_async),
+            _ => Err(MpvError::InvalidParameter),
+        };
+        Some(reply(request_id, res))
+    }
+
+    fn command(&mut self, request_id: Value, args: &Value, is_async: bool) -> Option<Value> {
+        let Some(args) = json_to_node(args) else {
+            return Some(reply(request_id, Err(MpvError::InvalidParameter)));
+        };
+        if !is_async {
+            let res = self.ctx.command_node(args, true);
+            return Some(reply(request_id, res.map_err(|err| err.code)));
+        }
+        let id = self.next_async_id;
+        self.next_async_id += 1;
+        match self.ctx.command_node_async(args, id) {
+            Ok(()) => {
+                self.pending.insert(id, request_id);
+                None
+            }
+            Err(err) => Some(reply(request_id, Err(err.code))),
+        }
+    }
+
+    /// Run the commands that are implemented by the IPC layer instead of the
+    /// player. Returns `None` for all other commands.
+    fn special_command(&mut self, args: &[Value]) -> Option<Result<Option<Node>, MpvError>> {
+        let str_arg = |i: usize| args.get(i).and_then(Value::as_str).map(str::to_owned);
+        let int_arg = |i: usize| args.get(i).and_then(Value::as_u64);
+        let invalid = Err(MpvError::InvalidParameter);
+        let res = match args.first()?.as_str()? {
+            "client_name" => Ok(Some(Node::String(self.ctx.name()))),
+            "get_time_us" => Ok(Some(Node::Int64(self.ctx.get_time_us()))),
+            "get_version" => Ok(Some(Node::Int64(client_api_version() as i64))),
+            "get_property" => match str_arg(1) {
+                Some(name) => self.ctx.get_property(name).map(Some).map_err(|e| e.code),
+                None => invalid,
+            },
+            "get_property_string" => match str_arg(1) {
+                Some(name) => self
+                    .ctx
+                    .get_property_string(name)
+                    .map(|s| Some(Node::String(s)))
+                    .map_err(|e| e.code),
+                None => invalid,
+            },
+            "set_property" => match (str_arg(1), args.get(2).and_then(json_to_node)) {
+                (Some(name), Some(value)) => self
+                    .ctx
+                    .set_property(name, value)
+                    .map(|_| None)
+                    .map_err(|e| e.code),
+                _ => invalid,
+            },
+            "set_property_string" => match (str_arg(1), str_arg(2)) {
+                (Some(name), Some(value)) => self
+                    .ctx
+                    .set_property(name, Node::String(value))
+                    .map(|_| None)
+                    .map_err(|e| e.code),
+                _ => invalid,
+            },
+            cmd @ ("observe_property" | "observe_property_string") => {
+                let format = match cmd {
+                    "observe_property" => MpvFormat::Node,
+                    _ => MpvFormat::String,
+                };
+                match (int_arg(1), str_arg(2)) {
+                    (Some(id), Some(name)) => self
+                        .ctx
+                        .observe_property(name, format, id)
+                        .map(|_| None)
+                        .map_err(|e| e.code),
+                    _ => invalid,
+                }
+            }
+            "unobserve_property" => match int_arg(1) {
+                Some(id) => self
+                    .ctx
+                    .unobserve_property(id)
+                    .map(|_| None)
+                    .map_err(|e| e.code),
+                None => invalid,
+            },
+            "request_log_messages" => match str_arg(1) {
+                Some(level) => self
+                    .ctx
+                    .request_log_messages(&level)
+                    .map(|_| None)
+                    .map_err(|e| e.code),
+                None => invalid,
+            },
+            cmd @ ("enable_event" | "disable_event") => match str_arg(1) {
+                Some(name) => self.request_event(&name, cmd == "enable_event"),
+                None => invalid,
+            },
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// Enable or disable an event by name, or all events with "all".
+    fn request_event(&mut self, name: &str, enable: bool) -> Result<Option<Node>, MpvError> {
+        let mut found = false;
+        for id in 1..64 {
+            let raw = unsafe { mpv_event_name(id) };
+            if raw.is_null() {
+                continue;
+            }
+            let Some(event) = make_rust_string_const(raw) else {
+                continue;
+            };
+            if name == "all" || name == event {
+                found = true;
+                let status = unsafe { mpv_request_event(self.ctx.as_raw(), id, enable as _) };
+                // Some events can't be disabled; that's only an error if the
+                // event was asked for by name.
+                if name != "all" {
+                    MpvError::check(status)?;
+                }
+            }
+        }
+        if found {
+            Ok(None)
+        } else {
+            Err(MpvError::InvalidParameter)
+        }
+    }
+}
+
+/// Build a reply message in mpv's format.
+fn reply(request_id: Value, res: Result<Option<Node>, MpvError>) -> Value {
+    let mut msg = Map::new();
+    match res {
+        Ok(data) => {
+            msg.insert(
+                "data".into(),
+                data.as_ref().map_or(Value::Null, node_to_json),
+            );
+            msg.insert("error".into(), "success".into());
+        }
+        Err(err) => {
+            msg.insert("error".into(), err.to_string().into());
+        }
+    }
+    msg.insert("request_id".into(), request_id);
+    Value::Object(msg)
+}
+
+fn send(writer: &mut UnixStream, msg: &Value) -> io::Result<()> {
+    let mut line = msg.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes())
+}
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt};
+
+use super::{client::MpvHandle, error::Error, event::Event, input::InputSection};
+
+type MessageHandler = Box<dyn FnMut(&mut MpvHandle, &[String])>;
+
+/// Routes client messages to handlers by their first argument, the message
+/// name, like mp.register_script_message() does for Lua scripts.
+///
+/// Messages are sent by scripts and other clients with "script-message" or
+/// "script-message-to", and arrive as `Event::ClientMessage`. Handlers get
+/// the remaining arguments.
+#[derive(Default)]
+pub struct MessageDispatcher {
+    handlers: HashMap<String, MessageHandler>,
+}
+
+impl fmt::Debug for MessageDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageDispatcher")
+            .field("messages", &self.handlers.keys())
+            .finish()
+    }
+}
+
+impl MessageDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `handler` for messages named `name`. This replaces a previous
+    /// handler for the same name.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        handler: impl FnMut(&mut MpvHandle, &[String]) + 'static,
+    ) {
+        self.handlers.insert(name.into(), Box::new(handler));
+    }
+
+    /// Remove the handler for `name`. Returns whether there was one.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.handlers.remove(name).is_some()
+    }
+
+    /// Handle the messages sent by the closure bindings of `section`. The
+    /// section's name is used as message name.
+    pub fn register_section(&mut self, mut section: InputSection) {
+        let name = section.name().to_owned();
+        self.register(name, move |ctx, args| {
+            if let [key] = args {
+                section.press(ctx, key);
+            }
+        });
+    }
+
+    /// Call the handler for `event`, if it is a client message with a
+    /// registered name. Returns whether a handler was called.
+    pub fn handle_event(&mut self, ctx: &mut MpvHandle, event: &Event) -> bool {
+        let Event::ClientMessage { args } = event else {
+            return false;
+        };
+        self.handle_message(ctx, args)
+    }
+
+    /// Same as `handle_event()`, with the arguments of a client message.
+    pub fn handle_message(&mut self, ctx: &mut MpvHandle, args: &[String]) -> bool {
+        let Some((name, args)) = args.split_first() else {
+            return false;
+        };
+        match self.handlers.get_mut(name) {
+            Some(handler) => {
+                handler(ctx, args);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl MpvHandle {
+    /// Send a message to all clients, including all scripts ("script-message").
+    pub fn script_message(&mut self, args: &[&str]) -> Result<(), Error> {
+        let mut cmd = vec!["script-message".to_owned()];
+        cmd.extend(args.iter().map(|&arg| arg.to_owned()));
+        self.command(cmd, false).map(|_| ())
+    }
+
+    /// Send a message to the client or script named `target`
+    /// ("script-message-to"). Fails if there is no such client.
+    pub fn script_message_to(&mut self, target: &str, args: &[&str]) -> Result<(), Error> {
+        let mut cmd = vec!["script-message-to".to_owned(), target.to_owned()];
+        cmd.extend(args.iter().map(|&arg| arg.to_owned()));
+        self.command(cmd, false).map(|_| ())
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{
+    error::{Error, MpvError, Operation},
+    event::Event,
+    node::{MpvFormat, Node, Property},
+    player::Player,
+    util::command_name,
+};
+
+/// An in-memory `Player` for testing code that drives a player, without
+/// running mpv.
+///
+/// Commands are recorded instead of run, and return scripted results.
+/// Properties are plain values: they can be scripted with `set()`, and are
+/// changed by `set_property()`. Values are returned as they were set, whatever
+/// format is asked for. Observing a property works as in mpv: there is
+/// a `PropertyChange` event with the current value right away, and another one
+/// whenever the value changes. Further events are scripted with `push_event()`.
+///
+/// Nothing ever happens on its own, so `wait_event()` never blocks: it returns
+/// `None` as soon as there are no more events. Waiting without timeout when
+/// there are none would block forever, so that panics with "no scripted
+/// events" instead.
+#[derive(Debug, Clone, Default)]
+pub struct MockPlayer {
+    properties: HashMap<String, Node>,
+    commands: Vec<Node>,
+    /// Scripted results, by command name, used in order.
+    results: HashMap<String, VecDeque<Result<Option<Node>, MpvError>>>,
+    events: VecDeque<Event>,
+    /// Observed properties, as (name, reply_userdata).
+    observed: Vec<(String, u64)>,
+}
+
+impl MockPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a property, as if the player changed it. Observers get a
+    /// `PropertyChange` event if the value is different.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Node>) {
+        let name = name.into();
+        let value = value.into();
+        if self.properties.get(&name) == Some(&value) {
+            return;
+        }
+        self.properties.insert(name.clone(), value);
+        self.notify(&name);
+    }
+
+    /// Remove a property, after which getting it fails with
+    /// `MpvError::PropertyUnavailable`. Observers get a `PropertyChange` event
+    /// without data.
+    pub fn unset(&mut self, name: &str) {
+        if self.properties.remove(name).is_some() {
+            self.notify(name);
+        }
+    }
+
+    /// Current value of a property, e.g. to check what the code under test set.
+    pub fn get(&self, name: &str) -> Option<&Node> {
+        self.properties.get(name)
+    }
+
+    /// Queue an event for `wait_event()`.
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// Make the next call of the command `name` return `result`. Results for
+    /// the same command are used in the order they were added; once they are
+    /// used up, the command succeeds without result.
+    pub fn push_result(&mut self, name: impl Into<String>, result: Result<Option<Node>, MpvError>) {
+        self.results
+            .entry(name.into())
+            .or_default()
+            .push_back(result);
+    }
+
+    /// All commands run so far, in order. Commands given as list of strings
+    /// are recorded as `Node::Array` of `Node::String`.
+    pub fn commands(&self) -> &[Node] {
+        &self.commands
+    }
+
+    /// Return the commands run so far, and forget them.
+    pub fn take_commands(&mut self) -> Vec<Node> {
+        std::mem::take(&mut self.commands)
+    }
+
+    /// Whether a property is observed.
+    pub fn is_observed(&self, name: &str) -> bool {
+        self.observed.iter().any(|(n, _)| n == name)
+    }
+
+    /// Queue a `PropertyChange` event with the current value for every
+    /// observer of the property.
+    fn notify(&mut self, name: &str) {
+        for (_, reply_userdata) in self.observed.iter().filter(|(n, _)| n == name) {
+            self.events.push_back(Event::PropertyChange {
+                result: Ok(Some(Property {
+                    name: name.to_owned(),
+                    data: self.properties.get(name).cloned(),
+                })),
+                reply_userdata: *reply_userdata,
+            });
+        }
+    }
+
+    fn run(&mut self, name: String, command: Node) -> Result<Option<Node>, Error> {
+        self.commands.push(command);
+        match self.results.get_mut(&name).and_then(VecDeque::pop_front) {
+            Some(Ok(res)) => Ok(res),
+            Some(Err(code)) => Err(Error::new(Operation::Command(name), code)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Player for MockPlayer {
+    fn command(&mut self, args: Vec<String>, require_result: bool) -> Result<Option<Node>, Error> {
+        let name = args.first().cloned().unwrap_or_default();
+        let command = Node::Array(args.into_iter().map(Node::String).collect());
+        let res = self.run(name, command)?;
+        Ok(res.filter(|_| require_result))
+    }
+
+    fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error> {
+        let res = self.run(command_name(&arg), arg)?;
+        Ok(res.filter(|_| require_result))
+    }
+
+    fn get_property(&mut self, name: String) -> Result<Node, Error> {
+        match self.properties.get(&name) {
+            Some(value) => Ok(value.clone()),
+            None => Err(Error::new(
+                Operation::GetProperty(name),
+                MpvError::PropertyUnavailable,
+            )),
+        }
+    }
+
+    fn set_property(&mut self, name: String, node: Node) -> Result<(), Error> {
+        self.set(name, node);
+        Ok(())
+    }
+
+    fn observe_property(
+        &mut self,
+        name: String,
+        _format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        self.observed.push((name.clone(), reply_userdata));
+        self.events.push_back(Event::PropertyChange {
+            result: Ok(Some(Property {
+                data: self.properties.get(&name).cloned(),
+                name,
+            })),
+            reply_userdata,
+        });
+        Ok(())
+    }
+
+    fn unobserve_property(&mut self, reply_userdata: u64) -> Result<(), Error> {
+        self.observed.retain(|(_, id)| *id != reply_userdata);
+        Ok(())
+    }
+
+    fn wait_event(&mut self, timeout: f64) -> Option<Event> {
+        let event = self.events.pop_front();
+        if event.is_none() && timeout < 0.0 {
+            panic!("no scripted events, waiting for one would block forever");
+        }
+        event
+    }
+}
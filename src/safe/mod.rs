@@ -1,11 +1,85 @@
+// Most conversions to and from the C types are only used by the code that
+// calls into libmpv.
+#![cfg_attr(not(feature = "libmpv"), allow(dead_code))]
+
+pub mod ass;
+#[cfg(feature = "libmpv")]
+pub mod async_wait;
+#[cfg(feature = "libmpv")]
+pub mod builder;
+#[cfg(feature = "libmpv")]
 pub mod client;
+#[cfg(feature = "libmpv")]
+pub mod encoder;
 pub mod error;
 pub mod event;
+#[cfg(feature = "libmpv")]
+pub mod event_loop;
+#[cfg(feature = "libmpv")]
+pub mod event_thread;
+pub mod filter;
+#[cfg(feature = "libmpv")]
+pub mod input;
+#[cfg(all(unix, feature = "ipc"))]
+pub mod ipc;
+#[cfg(feature = "libmpv")]
+pub mod message;
+pub mod mock;
 pub mod node;
+#[cfg(feature = "libmpv")]
+pub mod osd;
+#[cfg(feature = "libmpv")]
+pub mod overlay;
+pub mod player;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod resync;
+pub mod screenshot;
+#[cfg(feature = "libmpv")]
+pub mod seek;
+pub mod state;
+#[cfg(feature = "libmpv")]
+pub mod subtitle;
+#[cfg(feature = "libmpv")]
+pub mod thumbnail;
 
 pub(crate) mod util;
 
+pub use ass::*;
+#[cfg(feature = "libmpv")]
+pub use async_wait::*;
+#[cfg(feature = "libmpv")]
+pub use builder::*;
+#[cfg(feature = "libmpv")]
 pub use client::*;
+#[cfg(feature = "libmpv")]
+pub use encoder::*;
 pub use error::*;
 pub use event::*;
+#[cfg(feature = "libmpv")]
+pub use event_loop::*;
+#[cfg(feature = "libmpv")]
+pub use event_thread::*;
+pub use filter::*;
+#[cfg(feature = "libmpv")]
+pub use input::*;
+#[cfg(feature = "libmpv")]
+pub use message::*;
+pub use mock::*;
 pub use node::*;
+#[cfg(feature = "libmpv")]
+pub use osd::*;
+#[cfg(feature = "libmpv")]
+pub use overlay::*;
+pub use player::*;
+#[cfg(feature = "record")]
+pub use record::*;
+pub use resync::*;
+pub use screenshot::*;
+#[cfg(feature = "libmpv")]
+pub use seek::*;
+pub use state::*;
+#[cfg(feature = "libmpv")]
+pub use subtitle::*;
+#[cfg(feature = "libmpv")]
+pub use thumbnail::*;
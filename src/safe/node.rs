@@ -1,23 +1,30 @@
 use std::{
     collections::HashMap,
-    ffi::{CStr, CString},
-    ptr::{null, null_mut},
+    ffi::CString,
+    ptr::{null_mut, slice_from_raw_parts_mut},
+    slice,
 };
 
+use libc::c_char;
+
 use crate::{raw::*, safe::util::make_c_string};
 
-use super::util::{make_rust_string, make_rust_string_const};
+use super::util::make_rust_string_const;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MpvFormat {
     String,
     OsdString,
     Flag,
     Int64,
     Float64,
+    /// Any type, delivered as `Node`.
+    Node,
 }
 
 impl MpvFormat {
+    // The format constants are lowercase, as generated by bindgen.
+    #[allow(non_upper_case_globals)]
     pub(crate) fn from_mpv_format(format: mpv_format) -> Option<Self> {
         match format {
             mpv_format_MPV_FORMAT_STRING => Some(Self::String),
@@ -25,6 +32,7 @@ impl MpvFormat {
             mpv_format_MPV_FORMAT_FLAG => Some(Self::Flag),
             mpv_format_MPV_FORMAT_INT64 => Some(Self::Int64),
             mpv_format_MPV_FORMAT_DOUBLE => Some(Self::Float64),
+            mpv_format_MPV_FORMAT_NODE => Some(Self::Node),
             _ => None,
         }
     }
@@ -36,11 +44,13 @@ impl MpvFormat {
             Self::Flag => mpv_format_MPV_FORMAT_FLAG,
             Self::Int64 => mpv_format_MPV_FORMAT_INT64,
             Self::Float64 => mpv_format_MPV_FORMAT_DOUBLE,
+            Self::Node => mpv_format_MPV_FORMAT_NODE,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Node {
     /// It returns the raw property string, like using ${=property} in input.conf (see input.rst).
     ///
@@ -61,6 +71,7 @@ pub enum Node {
     /// as integer type.
     Flag(bool),
     Int64(i64),
+    #[cfg_attr(feature = "serde", serde(with = "float_serde"))]
     Float64(f64),
     Array(Vec<Node>),
     ByteArray(Vec<u8>),
@@ -68,23 +79,63 @@ pub enum Node {
     Node(Box<Node>),
 }
 
+/// Serde for `Node::Float64`. JSON has no NaN or infinities (serde_json turns
+/// them into null), so they are written as the strings "NaN", "inf" and
+/// "-inf" instead.
+#[cfg(feature = "serde")]
+mod float_serde {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        match *value {
+            value if value.is_finite() => serializer.serialize_f64(value),
+            value if value.is_nan() => serializer.serialize_str("NaN"),
+            f64::INFINITY => serializer.serialize_str("inf"),
+            _ => serializer.serialize_str("-inf"),
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Float {
+        Number(f64),
+        Name(String),
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        let name = match Float::deserialize(deserializer)? {
+            Float::Number(value) => return Ok(value),
+            Float::Name(name) => name,
+        };
+        match name.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "inf" => Ok(f64::INFINITY),
+            "-inf" => Ok(f64::NEG_INFINITY),
+            _ => Err(D::Error::custom(format!("invalid float {name:?}"))),
+        }
+    }
+}
+
 impl Node {
+    /// Convert an mpv_node into a `Node`. All data is copied, so the source node
+    /// still has to be freed by its owner (e.g. with mpv_free_node_contents() if
+    /// it was returned by mpv).
     pub(crate) fn from_mpv_node(node: mpv_node) -> Option<Node> {
         let format = node.format;
         match format {
             mpv_format_MPV_FORMAT_STRING => {
                 let data = unsafe { node.u.string };
-                let Some(data) = make_rust_string(data) else {
+                if data.is_null() {
                     return None;
-                };
-                Some(Node::String(data))
+                }
+                Some(Node::String(make_rust_string_const(data)?))
             }
             mpv_format_MPV_FORMAT_OSD_STRING => {
                 let data = unsafe { node.u.string };
-                let Some(data) = make_rust_string(data) else {
+                if data.is_null() {
                     return None;
-                };
-                Some(Node::OsdString(data))
+                }
+                Some(Node::OsdString(make_rust_string_const(data)?))
             }
             mpv_format_MPV_FORMAT_FLAG => {
                 let data = unsafe { node.u.flag };
@@ -101,21 +152,24 @@ impl Node {
             }
             mpv_format_MPV_FORMAT_BYTE_ARRAY => {
                 let data = unsafe { node.u.ba };
-                if data == null_mut() {
+                if data.is_null() {
                     return None;
                 }
                 let mpv_byte_array { data, size } = unsafe { *data };
-                let data = unsafe { Vec::from_raw_parts(data as *mut u8, size, size) };
-                Some(Node::ByteArray(data))
+                if data.is_null() || size == 0 {
+                    return Some(Node::ByteArray(vec![]));
+                }
+                let data = unsafe { slice::from_raw_parts(data as *const u8, size) };
+                Some(Node::ByteArray(data.to_vec()))
             }
             mpv_format_MPV_FORMAT_NODE_ARRAY => {
                 let data = unsafe { node.u.list };
-                if data == null_mut() {
+                if data.is_null() {
                     return None;
                 }
                 let mut arr = vec![];
-                let mpv_node_list { num, values, keys } = unsafe { *data };
-                if values != null_mut() {
+                let mpv_node_list { num, values, .. } = unsafe { *data };
+                if !values.is_null() {
                     for i in 0..num as usize {
                         let ptr = unsafe { values.add(i) };
                         if let Some(node) = Node::from_mpv_node(unsafe { *ptr }) {
@@ -128,19 +182,22 @@ impl Node {
             }
             mpv_format_MPV_FORMAT_NODE_MAP => {
                 let data = unsafe { node.u.list };
-                if data == null_mut() {
+                if data.is_null() {
                     return None;
                 }
                 let mut map = HashMap::new();
                 let mpv_node_list { num, values, keys } = unsafe { *data };
-                if values != null_mut() {
+                if !values.is_null() && !keys.is_null() {
                     for i in 0..num as usize {
                         let ptr = unsafe { values.add(i) };
                         let Some(node) = Node::from_mpv_node(unsafe { *ptr }) else {
                             continue;
                         };
-                        let key_ptr = unsafe { keys.add(i) };
-                        if let Some(key) = make_rust_string(unsafe { *key_ptr }) {
+                        let key_ptr = unsafe { *keys.add(i) };
+                        if key_ptr.is_null() {
+                            continue;
+                        }
+                        if let Some(key) = make_rust_string_const(key_ptr) {
                             map.insert(key, node);
                         }
                     }
@@ -152,6 +209,8 @@ impl Node {
         }
     }
 
+    /// Convert the node into an mpv_node allocated by Rust. The result must be
+    /// released with `free_mpv_node()`, never with mpv_free_node_contents().
     pub(crate) fn to_mpv_node(self) -> Option<mpv_node> {
         use crate::raw::mpv_node__bindgen_ty_1 as node_union;
         let mut node = mpv_node {
@@ -160,16 +219,12 @@ impl Node {
         };
         match self {
             Node::String(s) => {
-                let Some(data) = make_c_string(s) else {
-                    return None;
-                };
+                let data = make_c_string(s)?;
                 node.u = node_union { string: data };
                 node.format = mpv_format_MPV_FORMAT_STRING;
             }
             Node::OsdString(s) => {
-                let Some(data) = make_c_string(s) else {
-                    return None;
-                };
+                let data = make_c_string(s)?;
                 node.u = node_union { string: data };
                 node.format = mpv_format_MPV_FORMAT_OSD_STRING;
             }
@@ -187,14 +242,12 @@ impl Node {
                 node.format = mpv_format_MPV_FORMAT_DOUBLE;
             }
             Node::Array(vec) => {
-                let mut data = vec![];
-                for n in vec {
-                    if let Some(n) = n.to_mpv_node() {
-                        data.push(n);
-                    }
-                }
+                let data = vec
+                    .into_iter()
+                    .filter_map(Node::to_mpv_node)
+                    .collect::<Box<[mpv_node]>>();
                 let size = data.len();
-                let ptr = data.leak().as_mut_ptr();
+                let ptr = Box::into_raw(data) as *mut mpv_node;
                 let list = Box::into_raw(Box::new(mpv_node_list {
                     num: size as _,
                     values: ptr,
@@ -204,8 +257,9 @@ impl Node {
                 node.u = node_union { list };
             }
             Node::ByteArray(vec) => {
-                let size = vec.len();
-                let ptr = vec.leak().as_mut_ptr();
+                let data = vec.into_boxed_slice();
+                let size = data.len();
+                let ptr = Box::into_raw(data) as *mut u8;
                 let ba = Box::into_raw(Box::new(mpv_byte_array {
                     data: ptr as *mut _,
                     size,
@@ -217,14 +271,20 @@ impl Node {
                 let mut data = vec![];
                 let mut keys = vec![];
                 for (key, n) in map {
-                    if let Some(n) = n.to_mpv_node() {
-                        data.push(n);
-                        keys.push(make_c_string(key).unwrap());
+                    let Some(key) = make_c_string(key) else {
+                        continue;
+                    };
+                    match n.to_mpv_node() {
+                        Some(n) => {
+                            data.push(n);
+                            keys.push(key);
+                        }
+                        None => drop(unsafe { CString::from_raw(key) }),
                     }
                 }
                 let size = data.len();
-                let values = data.leak().as_mut_ptr();
-                let keys = keys.leak().as_mut_ptr();
+                let values = Box::into_raw(data.into_boxed_slice()) as *mut mpv_node;
+                let keys = Box::into_raw(keys.into_boxed_slice()) as *mut *mut c_char;
                 let list = Box::into_raw(Box::new(mpv_node_list {
                     num: size as _,
                     values,
@@ -233,14 +293,160 @@ impl Node {
                 node.format = mpv_format_MPV_FORMAT_NODE_MAP;
                 node.u = node_union { list };
             }
-            Node::Node(_) => unreachable!(),
+            Node::Node(node) => return node.to_mpv_node(),
         }
         Some(node)
     }
 }
 
+/// Release an mpv_node created by `Node::to_mpv_node()`.
+// The format constants are lowercase, as generated by bindgen.
+#[allow(non_upper_case_globals)]
+pub(crate) fn free_mpv_node(node: mpv_node) {
+    unsafe {
+        match node.format {
+            mpv_format_MPV_FORMAT_STRING | mpv_format_MPV_FORMAT_OSD_STRING => {
+                drop(CString::from_raw(node.u.string));
+            }
+            mpv_format_MPV_FORMAT_BYTE_ARRAY => {
+                let ba = Box::from_raw(node.u.ba);
+                drop(Box::from_raw(slice_from_raw_parts_mut(
+                    ba.data as *mut u8,
+                    ba.size,
+                )));
+            }
+            mpv_format_MPV_FORMAT_NODE_ARRAY | mpv_format_MPV_FORMAT_NODE_MAP => {
+                let list = Box::from_raw(node.u.list);
+                let num = list.num as usize;
+                let values = Box::from_raw(slice_from_raw_parts_mut(list.values, num));
+                for value in values.iter() {
+                    free_mpv_node(*value);
+                }
+                if !list.keys.is_null() {
+                    let keys = Box::from_raw(slice_from_raw_parts_mut(list.keys, num));
+                    for key in keys.iter() {
+                        drop(CString::from_raw(*key));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl From<String> for Node {
+    fn from(value: String) -> Self {
+        Node::String(value)
+    }
+}
+
+impl From<&str> for Node {
+    fn from(value: &str) -> Self {
+        Node::String(value.to_owned())
+    }
+}
+
+impl From<bool> for Node {
+    fn from(value: bool) -> Self {
+        Node::Flag(value)
+    }
+}
+
+impl From<i64> for Node {
+    fn from(value: i64) -> Self {
+        Node::Int64(value)
+    }
+}
+
+impl From<f64> for Node {
+    fn from(value: f64) -> Self {
+        Node::Float64(value)
+    }
+}
+
+impl From<Vec<Node>> for Node {
+    fn from(value: Vec<Node>) -> Self {
+        Node::Array(value)
+    }
+}
+
+impl From<Vec<u8>> for Node {
+    fn from(value: Vec<u8>) -> Self {
+        Node::ByteArray(value)
+    }
+}
+
+impl From<HashMap<String, Node>> for Node {
+    fn from(value: HashMap<String, Node>) -> Self {
+        Node::Map(value)
+    }
+}
+
+/// Rust types that property values convert to, for typed property access.
+pub trait PropertyValue: Sized {
+    /// The format to observe the property with.
+    const FORMAT: MpvFormat;
+
+    /// Convert a value. Returns `None` if the node has another type.
+    fn from_node(node: &Node) -> Option<Self>;
+}
+
+impl PropertyValue for String {
+    const FORMAT: MpvFormat = MpvFormat::String;
+
+    fn from_node(node: &Node) -> Option<Self> {
+        match node {
+            Node::String(value) | Node::OsdString(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for bool {
+    const FORMAT: MpvFormat = MpvFormat::Flag;
+
+    fn from_node(node: &Node) -> Option<Self> {
+        match node {
+            Node::Flag(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for i64 {
+    const FORMAT: MpvFormat = MpvFormat::Int64;
+
+    fn from_node(node: &Node) -> Option<Self> {
+        match node {
+            Node::Int64(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for f64 {
+    const FORMAT: MpvFormat = MpvFormat::Float64;
+
+    fn from_node(node: &Node) -> Option<Self> {
+        match node {
+            Node::Float64(value) => Some(*value),
+            Node::Int64(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for Node {
+    const FORMAT: MpvFormat = MpvFormat::Node;
+
+    fn from_node(node: &Node) -> Option<Self> {
+        Some(node.clone())
+    }
+}
+
 #[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Property {
     /// Name of the property.
     pub name: String,
@@ -250,16 +456,18 @@ pub struct Property {
 
 impl Property {
     pub(crate) fn from_mpv_property(property: mpv_event_property) -> Option<Self> {
-        let Some(name) = make_rust_string_const(property.name) else {
-            return None;
-        };
+        let name = make_rust_string_const(property.name)?;
         let mut res = Self { name, data: None };
         if property.data == null_mut() {
             return Some(res);
         }
-        let node = mpv_node {
-            format: property.format,
-            u: unsafe { *(property.data as *mut mpv_node__bindgen_ty_1) },
+        let node = if property.format == mpv_format_MPV_FORMAT_NODE {
+            unsafe { *(property.data as *mut mpv_node) }
+        } else {
+            mpv_node {
+                format: property.format,
+                u: unsafe { *(property.data as *mut mpv_node__bindgen_ty_1) },
+            }
         };
         res.data = Node::from_mpv_node(node);
         Some(res)
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use super::{
+    client::{MpvHandle, WeakClient},
+    error::Error,
+    node::Node,
+    player::Player,
+};
+
+/// Screen area covered by an overlay, in the overlay's coordinate system
+/// (`res_x` by `res_y`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub x0: i64,
+    pub y0: i64,
+    pub x1: i64,
+    pub y1: i64,
+}
+
+impl Bounds {
+    /// Decode the result of "osd-overlay" with compute_bounds. Returns `None`
+    /// if the overlay has no visible content.
+    pub fn from_node(node: &Node) -> Option<Self> {
+        let Node::Map(map) = node else {
+            return None;
+        };
+        let int = |map: &HashMap<String, Node>, key: &str| match map.get(key) {
+            Some(Node::Int64(value)) => Some(*value),
+            _ => None,
+        };
+        Some(Self {
+            x0: int(map, "x0")?,
+            y0: int(map, "y0")?,
+            x1: int(map, "x1")?,
+            y1: int(map, "y1")?,
+        })
+    }
+}
+
+/// An OSD overlay showing ASS events, drawn with the "osd-overlay" command.
+///
+/// Set the fields, then call `update()` to show the changes. The overlay is
+/// removed when it is dropped.
+///
+/// Overlays belong to the client that created them, so every overlay gets its
+/// own weak client handle. It is removed as well when the handle it was
+/// created from is dropped or terminated; after that, its methods fail.
+pub struct OsdOverlay {
+    ctx: WeakClient,
+    /// ASS events, one per line, e.g. from `AssBuilder::build()`. Only the
+    /// "Text" field of an event is given, and styles can't be defined; use
+    /// override tags.
+    pub data: String,
+    /// Width of the coordinate system. 0 derives it from `res_y` and the
+    /// window's aspect ratio.
+    pub res_x: u32,
+    /// Height of the coordinate system.
+    pub res_y: u32,
+    /// Stacking order among overlays. Higher values are drawn on top.
+    pub z: i64,
+    /// Whether the overlay is hidden. A hidden overlay still computes its
+    /// bounds.
+    pub hidden: bool,
+}
+
+impl OsdOverlay {
+    /// Create an empty overlay, with a 720 pixel high coordinate system.
+    pub fn new(ctx: &mut MpvHandle) -> Result<Self, Error> {
+        Ok(Self {
+            ctx: ctx.create_tied_client(c"osd-overlay")?,
+            data: String::new(),
+            res_x: 0,
+            res_y: 720,
+            z: 0,
+            hidden: false,
+        })
+    }
+
+    /// Show the overlay with the current fields.
+    pub fn update(&mut self) -> Result<(), Error> {
+        self.run(false).map(|_| ())
+    }
+
+    /// Same as `update()`, and return the area covered by the overlay, or
+    /// `None` if it has no visible content. Computing the bounds renders the
+    /// overlay, which is slower than `update()`.
+    pub fn update_with_bounds(&mut self) -> Result<Option<Bounds>, Error> {
+        let res = self.run(true)?;
+        Ok(res.as_ref().and_then(Bounds::from_node))
+    }
+
+    fn run(&mut self, compute_bounds: bool) -> Result<Option<Node>, Error> {
+        let args = HashMap::from([
+            ("name".to_owned(), Node::from("osd-overlay")),
+            ("id".to_owned(), Node::Int64(0)),
+            ("format".to_owned(), Node::from("ass-events")),
+            ("data".to_owned(), Node::String(self.data.clone())),
+            ("res_x".to_owned(), Node::Int64(self.res_x.into())),
+            ("res_y".to_owned(), Node::Int64(self.res_y.into())),
+            ("z".to_owned(), Node::Int64(self.z)),
+            ("hidden".to_owned(), Node::Flag(self.hidden)),
+            ("compute_bounds".to_owned(), Node::Flag(compute_bounds)),
+        ]);
+        self.ctx.command_node(Node::Map(args), compute_bounds)
+    }
+
+    /// Remove the overlay from the screen. A later `update()` shows it again.
+    pub fn remove(&mut self) -> Result<(), Error> {
+        let args = HashMap::from([
+            ("name".to_owned(), Node::from("osd-overlay")),
+            ("id".to_owned(), Node::Int64(0)),
+            ("format".to_owned(), Node::from("none")),
+            ("data".to_owned(), Node::from("")),
+        ]);
+        self.ctx.command_node(Node::Map(args), false).map(|_| ())
+    }
+}
+
+impl Drop for OsdOverlay {
+    fn drop(&mut self) {
+        let _ = self.remove();
+    }
+}
+
+impl MpvHandle {
+    /// Show a message on the OSD ("show-text"). `duration_ms` defaults to the
+    /// "osd-duration" option, and `level` to 1, which shows the message unless
+    /// the OSD is disabled. Property expansion like "${volume}" is applied.
+    pub fn show_text(
+        &mut self,
+        text: &str,
+        duration_ms: Option<u32>,
+        level: Option<u32>,
+    ) -> Result<(), Error> {
+        let duration = duration_ms.map_or("-1".into(), |ms| ms.to_string());
+        // Unlike mpv_command_string(), mpv_command() doesn't expand properties
+        // unless asked to.
+        let mut args = vec![
+            "expand-properties".into(),
+            "show-text".into(),
+            text.into(),
+            duration,
+        ];
+        args.extend(level.map(|level| level.to_string()));
+        self.command(args, false).map(|_| ())
+    }
+
+    /// Show the progress bar, the elapsed time and the total duration on the
+    /// OSD ("show-progress").
+    pub fn show_progress(&mut self) -> Result<(), Error> {
+        self.command(vec!["show-progress".into()], false)
+            .map(|_| ())
+    }
+}
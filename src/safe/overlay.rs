@@ -0,0 +1,190 @@
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, OwnedFd};
+
+use super::{
+    client::{MpvHandle, WeakClient},
+    error::{Error, MpvError, Operation},
+    player::Player,
+    screenshot::Frame,
+};
+
+enum Source {
+    /// Pixels owned by the overlay, passed to mpv by address.
+    Memory(Vec<u8>),
+    /// A file or shared memory object, which mpv maps.
+    #[cfg(unix)]
+    Fd { fd: OwnedFd, offset: u64 },
+}
+
+/// A BGRA bitmap shown over the video, with the "overlay-add" command.
+///
+/// The pixels must be premultiplied with alpha. The overlay owns the pixel
+/// memory (or file descriptor), which mpv may access until the overlay is
+/// removed. It is removed when it is dropped.
+///
+/// Overlay IDs (0 to 63) are shared by all clients of a player; adding an
+/// overlay with an ID that is in use replaces the other overlay. Like
+/// `OsdOverlay`, every bitmap overlay gets its own weak client handle, and
+/// stops working when the handle it was created from is dropped or
+/// terminated.
+pub struct BitmapOverlay {
+    ctx: WeakClient,
+    id: u8,
+    source: Source,
+    width: u32,
+    height: u32,
+    stride: usize,
+    x: i32,
+    y: i32,
+    display_size: Option<(u32, u32)>,
+    /// Whether the ID is ours to remove.
+    added: bool,
+}
+
+impl BitmapOverlay {
+    /// Show `frame` with its top left corner at (`x`, `y`), in video window
+    /// pixels. The frame's format must be "bgra".
+    pub fn new(ctx: &mut MpvHandle, id: u8, frame: Frame, x: i32, y: i32) -> Result<Self, Error> {
+        let invalid = || {
+            Error::new(
+                Operation::Command("overlay-add".into()),
+                MpvError::InvalidParameter,
+            )
+        };
+        let stride = usize::try_from(frame.stride).map_err(|_| invalid())?;
+        let size = stride * frame.height as usize;
+        if frame.format != "bgra" || stride < frame.width as usize * 4 || frame.data.len() < size {
+            return Err(invalid());
+        }
+        Self::add(
+            ctx,
+            id,
+            Source::Memory(frame.data),
+            frame.width,
+            frame.height,
+            stride,
+            x,
+            y,
+        )
+    }
+
+    /// Show a bitmap read from a file descriptor, e.g. of a memory-mapped file
+    /// or a shared memory object, starting at byte `offset`. Rows are `stride`
+    /// bytes apart, which must be at least `width * 4`.
+    #[cfg(unix)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_fd(
+        ctx: &mut MpvHandle,
+        id: u8,
+        fd: OwnedFd,
+        offset: u64,
+        width: u32,
+        height: u32,
+        stride: usize,
+        x: i32,
+        y: i32,
+    ) -> Result<Self, Error> {
+        if stride < width as usize * 4 {
+            return Err(Error::new(
+                Operation::Command("overlay-add".into()),
+                MpvError::InvalidParameter,
+            ));
+        }
+        Self::add(
+            ctx,
+            id,
+            Source::Fd { fd, offset },
+            width,
+            height,
+            stride,
+            x,
+            y,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add(
+        ctx: &mut MpvHandle,
+        id: u8,
+        source: Source,
+        width: u32,
+        height: u32,
+        stride: usize,
+        x: i32,
+        y: i32,
+    ) -> Result<Self, Error> {
+        let mut overlay = Self {
+            ctx: ctx.create_tied_client(c"bitmap-overlay")?,
+            id,
+            source,
+            width,
+            height,
+            stride,
+            x,
+            y,
+            display_size: None,
+            added: false,
+        };
+        overlay.update()?;
+        Ok(overlay)
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Move the overlay, in video window pixels.
+    pub fn set_position(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        self.x = x;
+        self.y = y;
+        self.update()
+    }
+
+    /// Scale the bitmap to the given size when it is drawn. `None` draws it at
+    /// its own size.
+    pub fn set_display_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        self.display_size = size;
+        self.update()
+    }
+
+    fn update(&mut self) -> Result<(), Error> {
+        let (file, offset) = match &self.source {
+            Source::Memory(data) => (format!("&{}", data.as_ptr() as usize), 0),
+            #[cfg(unix)]
+            Source::Fd { fd, offset } => (format!("@{}", fd.as_raw_fd()), *offset),
+        };
+        let mut args = vec![
+            "overlay-add".into(),
+            self.id.to_string(),
+            self.x.to_string(),
+            self.y.to_string(),
+            file,
+            offset.to_string(),
+            "bgra".into(),
+            self.width.to_string(),
+            self.height.to_string(),
+            self.stride.to_string(),
+        ];
+        if let Some((w, h)) = self.display_size {
+            args.extend([w.to_string(), h.to_string()]);
+        }
+        self.ctx.command(args, false)?;
+        self.added = true;
+        // Overlays outlive clients, and must not outlive the pixels.
+        self.ctx
+            .on_close(Some(vec!["overlay-remove".into(), self.id.to_string()]));
+        Ok(())
+    }
+}
+
+impl Drop for BitmapOverlay {
+    fn drop(&mut self) {
+        if !self.added {
+            return;
+        }
+        // The memory is freed after this, when the fields are dropped.
+        let _ = self
+            .ctx
+            .command(vec!["overlay-remove".into(), self.id.to_string()], false);
+    }
+}
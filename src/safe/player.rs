@@ -0,0 +1,226 @@
+use std::time::Instant;
+
+#[cfg(feature = "libmpv")]
+use super::client::MpvHandle;
+use super::{
+    error::{Error, MpvError, Operation, WaitError},
+    event::{EndFileReason, Event},
+    node::{MpvFormat, Node},
+    util::{deadline, time_left},
+};
+
+/// The operations shared by every way of controlling a player, so code can be
+/// written once for an in-process `MpvHandle` and a remote player.
+///
+/// The methods mirror those of `MpvHandle`, which implements this trait by
+/// calling them.
+pub trait Player {
+    /// Run a command given as a list of strings. With `require_result`, the
+    /// command's result is returned, if it has one.
+    fn command(&mut self, args: Vec<String>, require_result: bool) -> Result<Option<Node>, Error>;
+
+    /// Run a command given as an array or a map of named arguments.
+    fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error>;
+
+    fn get_property(&mut self, name: String) -> Result<Node, Error>;
+
+    fn set_property(&mut self, name: String, node: Node) -> Result<(), Error>;
+
+    /// Get `Event::PropertyChange` events with `reply_userdata` whenever the
+    /// property changes.
+    fn observe_property(
+        &mut self,
+        name: String,
+        format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error>;
+
+    /// Stop observing all properties that were observed with `reply_userdata`.
+    fn unobserve_property(&mut self, reply_userdata: u64) -> Result<(), Error>;
+
+    /// Wait for the next event, for at most `timeout` seconds. A negative
+    /// timeout waits forever, 0 only polls. Returns `None` on timeout.
+    fn wait_event(&mut self, timeout: f64) -> Option<Event>;
+
+    /// Wait until an event matching `pred` arrives, and return it. Other
+    /// events are discarded. Waits at most `timeout` seconds, or forever if
+    /// it's negative.
+    ///
+    /// Rather than waiting for an event that won't come, this fails if a file
+    /// fails to load (with the error of `Event::EndFile`), or if the player
+    /// shuts down (with `MpvError::Uninitialized`). A timeout is reported as
+    /// `WaitError::Timeout`.
+    fn wait_for(
+        &mut self,
+        pred: impl FnMut(&Event) -> bool,
+        timeout: f64,
+    ) -> Result<Event, WaitError>
+    where
+        Self: Sized,
+    {
+        wait_until(self, pred, deadline(timeout))?.ok_or(WaitError::Timeout(Operation::WaitEvent))
+    }
+
+    /// Load a file or URL, replacing the current one, and wait until it's
+    /// loaded (`Event::FileLoaded`). Fails with the error mpv reports if the
+    /// file can't be played.
+    ///
+    /// Events of the file played before are told apart by the playlist entry
+    /// ID that "loadfile" returns. Players that don't return it (e.g. older
+    /// mpv versions) are assumed to start the new file next.
+    fn load_and_wait(&mut self, url: &str) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        let res = self.command(vec!["loadfile".into(), url.into()], true)?;
+        let mut load = LoadWait::new(res);
+        loop {
+            let event = wait_until(self, LoadWait::wants, None)
+                .map_err(|err| Error::new(LoadWait::operation(), err.code))?;
+            if let Some(res) = event.and_then(|event| load.step(event)) {
+                return res;
+            }
+        }
+    }
+}
+
+/// `Player::wait_for()` until `deadline`, or forever with `None`. Returns
+/// `None` once the deadline has passed.
+pub(crate) fn wait_until<P: Player>(
+    player: &mut P,
+    mut pred: impl FnMut(&Event) -> bool,
+    deadline: Option<Instant>,
+) -> Result<Option<Event>, Error> {
+    loop {
+        let Some(event) = player.wait_event(time_left(deadline)) else {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(None);
+            }
+            continue;
+        };
+        if pred(&event) {
+            return Ok(Some(event));
+        }
+        if let Some(err) = wait_error(&event) {
+            return Err(err);
+        }
+    }
+}
+
+/// The error that ends a wait for an event that will no longer come.
+pub(crate) fn wait_error(event: &Event) -> Option<Error> {
+    match event {
+        Event::EndFile {
+            reason: EndFileReason::Error(err),
+            ..
+        } => Some(Error::new(Operation::WaitEvent, *err)),
+        Event::Shutdown => Some(Error::new(Operation::WaitEvent, MpvError::Uninitialized)),
+        _ => None,
+    }
+}
+
+/// The playlist entry ID in the result of "loadfile", if the player returns
+/// it (mpv 0.33 and later).
+pub(crate) fn playlist_entry_id(res: Option<Node>) -> Option<i64> {
+    match res {
+        Some(Node::Map(map)) => match map.get("playlist_entry_id") {
+            Some(Node::Int64(id)) => Some(*id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Tracks the events of a "loadfile" until the file is loaded, for
+/// `Player::load_and_wait()` and its async version.
+pub(crate) struct LoadWait {
+    entry_id: Option<i64>,
+    started: bool,
+}
+
+impl LoadWait {
+    /// `res` is the result of the "loadfile" command.
+    pub(crate) fn new(res: Option<Node>) -> Self {
+        Self {
+            entry_id: playlist_entry_id(res),
+            started: false,
+        }
+    }
+
+    pub(crate) fn operation() -> Operation {
+        Operation::Command("loadfile".into())
+    }
+
+    pub(crate) fn wants(event: &Event) -> bool {
+        matches!(
+            event,
+            Event::StartFile { .. } | Event::EndFile { .. } | Event::FileLoaded
+        )
+    }
+
+    /// Handle an event, and return the result once the wait is over.
+    pub(crate) fn step(&mut self, event: Event) -> Option<Result<(), Error>> {
+        match event {
+            Event::StartFile { playlist_entry_id }
+                if self.entry_id.is_none() || self.entry_id == Some(playlist_entry_id) =>
+            {
+                self.entry_id = Some(playlist_entry_id);
+                self.started = true;
+            }
+            Event::FileLoaded if self.started => return Some(Ok(())),
+            Event::EndFile {
+                reason,
+                playlist_entry_id,
+                playlist_insert_id,
+                ..
+            } if self.entry_id == Some(playlist_entry_id) => match reason {
+                // A playlist: wait for its first entry instead.
+                EndFileReason::Redirect if playlist_insert_id > 0 => {
+                    self.entry_id = Some(playlist_insert_id);
+                    self.started = false;
+                }
+                EndFileReason::Error(err) => return Some(Err(Error::new(Self::operation(), err))),
+                // Stopped by something else, e.g. another "loadfile".
+                _ => return Some(Err(Error::new(Self::operation(), MpvError::LoadingFailed))),
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(feature = "libmpv")]
+impl Player for MpvHandle {
+    fn command(&mut self, args: Vec<String>, require_result: bool) -> Result<Option<Node>, Error> {
+        MpvHandle::command(self, args, require_result)
+    }
+
+    fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error> {
+        MpvHandle::command_node(self, arg, require_result)
+    }
+
+    fn get_property(&mut self, name: String) -> Result<Node, Error> {
+        MpvHandle::get_property(self, name)
+    }
+
+    fn set_property(&mut self, name: String, node: Node) -> Result<(), Error> {
+        MpvHandle::set_property(self, name, node)
+    }
+
+    fn observe_property(
+        &mut self,
+        name: String,
+        format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        MpvHandle::observe_property(self, name, format, reply_userdata)
+    }
+
+    fn unobserve_property(&mut self, reply_userdata: u64) -> Result<(), Error> {
+        MpvHandle::unobserve_property(self, reply_userdata).map(|_| ())
+    }
+
+    fn wait_event(&mut self, timeout: f64) -> Option<Event> {
+        MpvHandle::wait_event(self, timeout)
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "libmpv")]
+use super::client::MpvHandle;
+use super::{event::Event, util::timeout_duration};
+
+/// An event, with the time it was received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// mpv's internal time (mpv_get_time_ns()) when the event was received.
+    /// Only differences between these are meaningful.
+    pub time_ns: i64,
+    pub event: Event,
+}
+
+/// Writes a trace of events, one JSON object per line, which can be read back
+/// with `EventReplayer`.
+///
+/// Requires the `record` feature.
+pub struct EventRecorder<W: Write> {
+    writer: W,
+    /// The first write error, reported by `finish()`.
+    error: Option<io::Error>,
+}
+
+impl EventRecorder<BufWriter<File>> {
+    /// Record into a new file, replacing an existing one.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> EventRecorder<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Append an event to the trace.
+    pub fn record(&mut self, time_ns: i64, event: &Event) -> io::Result<()> {
+        let mut line = serde_json::to_string(&RecordedEvent {
+            time_ns,
+            event: event.clone(),
+        })?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())
+    }
+
+    /// Wait for an event like `MpvHandle::wait_event()`, and record it with
+    /// the current time.
+    ///
+    /// The event is returned even if recording it failed, so the application
+    /// keeps working; the error is reported by `finish()`, and nothing is
+    /// recorded after it.
+    #[cfg(feature = "libmpv")]
+    pub fn wait_event(&mut self, ctx: &mut MpvHandle, timeout: f64) -> Option<Event> {
+        let event = ctx.wait_event(timeout)?;
+        if self.error.is_none() {
+            if let Err(err) = self.record(ctx.get_time_ns(), &event) {
+                self.error = Some(err);
+            }
+        }
+        Some(event)
+    }
+
+    /// Flush the trace, and return the writer. Fails with the first error
+    /// that happened while recording.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Plays back a recorded trace through a `wait_event()` like
+/// `MpvHandle::wait_event()`, e.g. to reproduce a bug report against
+/// application code without running mpv.
+///
+/// By default, events are returned as fast as they are asked for. With
+/// `realtime(true)`, the original time between events is kept.
+///
+/// Requires the `record` feature.
+#[derive(Debug, Clone)]
+pub struct EventReplayer {
+    events: VecDeque<RecordedEvent>,
+    realtime: bool,
+    /// When replay started, and the time of the first event.
+    start: Option<(Instant, i64)>,
+    time_ns: Option<i64>,
+}
+
+impl EventReplayer {
+    pub fn new(events: Vec<RecordedEvent>) -> Self {
+        Self {
+            events: events.into(),
+            realtime: false,
+            start: None,
+            time_ns: None,
+        }
+    }
+
+    /// Read a trace written by `EventRecorder`. Fails with
+    /// `io::ErrorKind::InvalidData` if a line can't be parsed.
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+        let mut events = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            events.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self::new(events))
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Whether to wait between events as long as the recording did.
+    pub fn realtime(mut self, enable: bool) -> Self {
+        self.realtime = enable;
+        self
+    }
+
+    /// Return the next event. In realtime mode, wait until it is due, but for
+    /// at most `timeout` seconds (forever if negative), and return `None` if it
+    /// isn't due by then. Returns `None` at the end of the trace.
+    pub fn wait_event(&mut self, timeout: f64) -> Option<Event> {
+        let next = self.events.front()?;
+        if self.realtime {
+            let (start, start_ns) = *self.start.get_or_insert((Instant::now(), next.time_ns));
+            let offset = u64::try_from(next.time_ns - start_ns).unwrap_or(0);
+            let due = start + Duration::from_nanos(offset);
+            let wait = due.saturating_duration_since(Instant::now());
+            if let Some(timeout) = timeout_duration(timeout).filter(|&timeout| wait > timeout) {
+                thread::sleep(timeout);
+                return None;
+            }
+            thread::sleep(wait);
+        }
+        let next = self.events.pop_front()?;
+        self.time_ns = Some(next.time_ns);
+        Some(next.event)
+    }
+
+    /// Recorded time of the event returned last.
+    pub fn time_ns(&self) -> Option<i64> {
+        self.time_ns
+    }
+
+    /// Number of events left.
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use super::{
+    error::Error,
+    event::Event,
+    node::{MpvFormat, Node, Property},
+    player::Player,
+};
+
+fn expand_text(player: &mut impl Player, text: String) -> Option<String> {
+    match player.command(vec!["expand-text".into(), text], true) {
+        Ok(Some(Node::String(text))) => Some(text),
+        _ => None,
+    }
+}
+
+/// Read a property like an observer in `format` would get it. `None` if it's
+/// unavailable.
+pub(crate) fn read_property(
+    player: &mut impl Player,
+    name: &str,
+    format: MpvFormat,
+) -> Option<Node> {
+    let node = player.get_property(name.into()).ok()?;
+    match (format, node) {
+        (MpvFormat::Node, node) => Some(node),
+        (MpvFormat::String, node @ Node::String(_)) => Some(node),
+        // Only mpv knows how other types are formatted.
+        (MpvFormat::String, _) => expand_text(player, format!("${{={name}}}")).map(Node::String),
+        (MpvFormat::OsdString, _) => {
+            expand_text(player, format!("${{{name}}}")).map(Node::OsdString)
+        }
+        (MpvFormat::Flag, node @ Node::Flag(_)) => Some(node),
+        (MpvFormat::Int64, node @ Node::Int64(_)) => Some(node),
+        (MpvFormat::Float64, node @ Node::Float64(_)) => Some(node),
+        (MpvFormat::Float64, Node::Int64(value)) => Some(Node::Float64(value as f64)),
+        _ => None,
+    }
+}
+
+/// The observed properties of a player, to read again after events were lost,
+/// for `ResyncPlayer` and `MpvHandle`.
+#[derive(Debug, Default)]
+pub(crate) struct Resync {
+    observed: Vec<(String, MpvFormat, u64)>,
+    /// Property changes made up after events were lost, returned before any
+    /// new events.
+    pending: VecDeque<Event>,
+    overflows: u64,
+}
+
+impl Resync {
+    pub(crate) fn observe(&mut self, name: String, format: MpvFormat, reply_userdata: u64) {
+        self.observed.push((name, format, reply_userdata));
+    }
+
+    pub(crate) fn unobserve(&mut self, reply_userdata: u64) {
+        self.observed.retain(|(_, _, id)| *id != reply_userdata);
+    }
+
+    /// The next made up event.
+    pub(crate) fn pop(&mut self) -> Option<Event> {
+        self.pending.pop_front()
+    }
+
+    pub(crate) fn overflows(&self) -> u64 {
+        self.overflows
+    }
+
+    /// Whether `event` is a change of a property that a resync reads again.
+    pub(crate) fn is_observed_change(&self, event: &Event) -> bool {
+        let Event::PropertyChange { reply_userdata, .. } = event else {
+            return false;
+        };
+        self.observed.iter().any(|(_, _, id)| id == reply_userdata)
+    }
+
+    /// Count an `Event::QueueOverflow` from `player`, and resync.
+    pub(crate) fn overflowed(&mut self, player: &mut impl Player) {
+        self.overflows += 1;
+        self.resync(player);
+    }
+
+    /// Read all observed properties, and queue a `PropertyChange` event for
+    /// each.
+    pub(crate) fn resync(&mut self, player: &mut impl Player) {
+        for (name, format, reply_userdata) in &self.observed {
+            let data = read_property(player, name, *format);
+            self.pending.push_back(Event::PropertyChange {
+                result: Ok(Some(Property {
+                    name: name.clone(),
+                    data,
+                })),
+                reply_userdata: *reply_userdata,
+            });
+        }
+    }
+}
+
+/// Wraps a player to recover from lost events.
+///
+/// mpv's event queue has a fixed size per client handle. If events aren't read
+/// quickly enough, e.g. during heavy seeking, events are dropped and
+/// `Event::QueueOverflow` is sent instead, so state kept from property changes
+/// may be stale. This wrapper remembers the observed properties; after passing
+/// on the overflow event, it reads all of them again and returns a
+/// `PropertyChange` event for each, so observers like `PlayerState` catch up.
+///
+/// `MpvHandle` does this itself; this wrapper is for other players, e.g.
+/// `IpcClient`, or `MockPlayer` to test the recovery.
+///
+/// Observe properties through the wrapper, so that it knows about them. To
+/// buffer more events than mpv does, see `MpvBuilder::buffer_events()`.
+#[derive(Debug)]
+pub struct ResyncPlayer<P> {
+    inner: P,
+    state: Resync,
+}
+
+impl<P: Player> ResyncPlayer<P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            state: Resync::default(),
+        }
+    }
+
+    pub fn inner(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    /// How often events were lost so far.
+    pub fn overflows(&self) -> u64 {
+        self.state.overflows()
+    }
+
+    /// Read all observed properties, and queue a `PropertyChange` event for
+    /// each. This happens after every `Event::QueueOverflow`.
+    pub fn resync(&mut self) {
+        self.state.resync(&mut self.inner);
+    }
+}
+
+impl<P: Player> Player for ResyncPlayer<P> {
+    fn command(&mut self, args: Vec<String>, require_result: bool) -> Result<Option<Node>, Error> {
+        self.inner.command(args, require_result)
+    }
+
+    fn command_node(&mut self, arg: Node, require_result: bool) -> Result<Option<Node>, Error> {
+        self.inner.command_node(arg, require_result)
+    }
+
+    fn get_property(&mut self, name: String) -> Result<Node, Error> {
+        self.inner.get_property(name)
+    }
+
+    fn set_property(&mut self, name: String, node: Node) -> Result<(), Error> {
+        self.inner.set_property(name, node)
+    }
+
+    fn observe_property(
+        &mut self,
+        name: String,
+        format: MpvFormat,
+        reply_userdata: u64,
+    ) -> Result<(), Error> {
+        self.inner
+            .observe_property(name.clone(), format, reply_userdata)?;
+        self.state.observe(name, format, reply_userdata);
+        Ok(())
+    }
+
+    fn unobserve_property(&mut self, reply_userdata: u64) -> Result<(), Error> {
+        self.inner.unobserve_property(reply_userdata)?;
+        self.state.unobserve(reply_userdata);
+        Ok(())
+    }
+
+    fn wait_event(&mut self, timeout: f64) -> Option<Event> {
+        if let Some(event) = self.state.pop() {
+            return Some(event);
+        }
+        let event = self.inner.wait_event(timeout)?;
+        if event == Event::QueueOverflow {
+            self.state.overflowed(&mut self.inner);
+        }
+        Some(event)
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use super::node::Node;
+#[cfg(feature = "libmpv")]
+use super::{
+    client::MpvHandle,
+    error::{Error, MpvError, Operation},
+};
+
+/// What a screenshot contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScreenshotMode {
+    /// The video with subtitles, scaled to the video size.
+    #[default]
+    Subtitles,
+    /// The video only, without subtitles or OSD.
+    Video,
+    /// The contents of the video window, with OSD and subtitles, as rendered.
+    Window,
+}
+
+impl ScreenshotMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Subtitles => "subtitles",
+            Self::Video => "video",
+            Self::Window => "window",
+        }
+    }
+}
+
+/// Raw image data of a screenshot, as returned by the "screenshot-raw"
+/// command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// Size of a row in bytes. This can be larger than `width * 4`, and can be
+    /// negative for bottom-up images in theory, though mpv doesn't produce them.
+    pub stride: i64,
+    /// Pixel format, e.g. "bgr0" or "bgra". Each pixel takes 4 bytes.
+    pub format: String,
+    /// The pixels, `stride * height` bytes.
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Decode the map returned by "screenshot-raw". Returns `None` if a field is
+    /// missing or has the wrong type, or if the rows don't fit in the data.
+    pub fn from_node(node: Node) -> Option<Self> {
+        let Node::Map(mut map) = node else {
+            return None;
+        };
+        let int = |map: &HashMap<String, Node>, key: &str| match map.get(key) {
+            Some(Node::Int64(value)) => Some(*value),
+            _ => None,
+        };
+        let width = u32::try_from(int(&map, "w")?).ok()?;
+        let height = u32::try_from(int(&map, "h")?).ok()?;
+        let stride = int(&map, "stride")?;
+        let Some(Node::String(format)) = map.remove("format") else {
+            return None;
+        };
+        let Some(Node::ByteArray(data)) = map.remove("data") else {
+            return None;
+        };
+        if stride.unsigned_abs() < width as u64 * 4
+            || (data.len() as u64) < stride.unsigned_abs() * height as u64
+        {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            stride,
+            format,
+            data,
+        })
+    }
+
+    /// Return the pixels of row `y`, without padding. `None` if `y` is out of
+    /// range.
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let start = self.stride.unsigned_abs() as usize * y as usize;
+        self.data.get(start..start + self.width as usize * 4)
+    }
+
+    /// Convert the frame to an RGBA image. Returns `None` for pixel formats
+    /// other than "bgr0", "bgra", "rgb0" and "rgba".
+    #[cfg(feature = "image")]
+    pub fn to_rgba_image(&self) -> Option<image::RgbaImage> {
+        let (bgr, alpha) = match self.format.as_str() {
+            "bgr0" => (true, false),
+            "bgra" => (true, true),
+            "rgb0" => (false, false),
+            "rgba" => (false, true),
+            _ => return None,
+        };
+        let mut pixels = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height {
+            for px in self.row(y)?.chunks_exact(4) {
+                let (r, b) = if bgr { (px[2], px[0]) } else { (px[0], px[2]) };
+                pixels.extend_from_slice(&[r, px[1], b, if alpha { px[3] } else { 255 }]);
+            }
+        }
+        image::RgbaImage::from_raw(self.width, self.height, pixels)
+    }
+}
+
+#[cfg(feature = "libmpv")]
+impl MpvHandle {
+    /// Take a screenshot and return the image data, instead of writing it to a
+    /// file. This runs the "screenshot-raw" command.
+    pub fn screenshot_raw(&mut self, mode: ScreenshotMode) -> Result<Frame, Error> {
+        let args = Node::Array(vec!["screenshot-raw".into(), mode.as_str().into()]);
+        let res = self.command_node(args, true)?;
+        res.and_then(Frame::from_node).ok_or(Error::new(
+            Operation::Command("screenshot-raw".into()),
+            MpvError::CommandError,
+        ))
+    }
+}
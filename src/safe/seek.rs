@@ -0,0 +1,269 @@
+use std::time::Instant;
+
+use super::{
+    client::{MpvHandle, WeakClient},
+    error::{Error, MpvError, Operation, WaitError},
+    event::{EndFileReason, Event},
+    node::Node,
+    player::Player,
+    util::{deadline, time_left},
+};
+
+/// How the target of a seek is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeekMode {
+    /// Seconds from the current position.
+    #[default]
+    Relative,
+    /// Seconds from the start of the file. Negative values are from the end.
+    Absolute,
+    /// Percent of the duration from the current position.
+    RelativePercent,
+    /// Percent of the duration from the start.
+    AbsolutePercent,
+}
+
+/// Whether a seek lands exactly on the target, or on a nearby keyframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeekPrecision {
+    /// Use the "hr-seek" option.
+    #[default]
+    Default,
+    /// Decode up to the target frame. Slower, but frame accurate.
+    Exact,
+    /// Go to the keyframe before the target. Fast.
+    Keyframes,
+}
+
+impl SeekMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Absolute => "absolute",
+            Self::RelativePercent => "relative-percent",
+            Self::AbsolutePercent => "absolute-percent",
+        }
+    }
+}
+
+impl SeekPrecision {
+    fn as_str(self) -> Option<&'static str> {
+        match self {
+            Self::Default => None,
+            Self::Exact => Some("exact"),
+            Self::Keyframes => Some("keyframes"),
+        }
+    }
+}
+
+/// Seeks and frame steps that return when they are done, i.e. when playback
+/// restarted at the new position.
+///
+/// The waiting is done on a weak client handle of its own, so events of the
+/// handle it was created from are not consumed. Once that handle is dropped
+/// or terminated, the navigator's methods fail.
+pub struct Navigator {
+    ctx: WeakClient,
+    /// Seconds to wait for playback to restart. Negative values wait forever.
+    pub timeout: f64,
+    /// `reply_userdata` of the last command.
+    last_id: u64,
+}
+
+/// How far past the start of a line `Navigator::sub_seek()` lands, like
+/// "sub-seek" does, so that rounding doesn't show the previous line.
+const SUB_SEEK_OFFSET: f64 = 0.01;
+
+impl Navigator {
+    /// Create a navigator, with a timeout of 10 seconds.
+    pub fn new(ctx: &mut MpvHandle) -> Result<Self, Error> {
+        Ok(Self {
+            ctx: ctx.create_tied_client(c"navigator")?,
+            timeout: 10.0,
+            last_id: 0,
+        })
+    }
+
+    /// Seek to `target` ("seek").
+    pub fn seek(
+        &mut self,
+        target: f64,
+        mode: SeekMode,
+        precision: SeekPrecision,
+    ) -> Result<(), WaitError> {
+        let flags = match precision.as_str() {
+            Some(precision) => format!("{}+{precision}", mode.as_str()),
+            None => mode.as_str().into(),
+        };
+        self.run(vec!["seek".into(), target.to_string(), flags])
+    }
+
+    /// Seek to exactly `time` seconds from the start.
+    pub fn seek_exact(&mut self, time: f64) -> Result<(), WaitError> {
+        self.seek(time, SeekMode::Absolute, SeekPrecision::Exact)
+    }
+
+    /// Seek to the keyframe at or before `time` seconds from the start.
+    pub fn seek_keyframe(&mut self, time: f64) -> Result<(), WaitError> {
+        self.seek(time, SeekMode::Absolute, SeekPrecision::Keyframes)
+    }
+
+    /// Go back to the position before the last seek ("revert-seek"). Seeks
+    /// done in quick succession count as one.
+    pub fn revert_seek(&mut self) -> Result<(), WaitError> {
+        self.run(vec!["revert-seek".into()])
+    }
+
+    /// Seek to the start of a subtitle line: 1 is the next line, -1 the
+    /// previous one, like "sub-seek". Returns `false` without seeking if there
+    /// is no such line, or no subtitle track.
+    pub fn sub_seek(&mut self, skip: i64) -> Result<bool, WaitError> {
+        // "sub-seek" doesn't tell whether it found a line. "sub-step" finds
+        // the same one, and moves the subtitles by its distance instead, which
+        // is undone right away.
+        let delay = self.float_property("sub-delay")?;
+        let time_pos = self.float_property("time-pos")?;
+        self.ctx
+            .command(vec!["sub-step".into(), skip.to_string()], false)?;
+        let stepped = self.float_property("sub-delay");
+        self.ctx
+            .set_property("sub-delay".into(), Node::Float64(delay))?;
+        let distance = delay - stepped?;
+        if distance == 0.0 {
+            return Ok(false);
+        }
+        self.seek_exact(time_pos + distance + SUB_SEEK_OFFSET)?;
+        Ok(true)
+    }
+
+    /// Show the next frame and pause. Unlike the default "frame-step", this
+    /// seeks to the frame, so it's also exact while playing.
+    ///
+    /// The seek mode of "frame-step" needs mpv 0.39 or later. Older versions
+    /// pause, and seek exactly by the duration of a frame ("container-fps").
+    pub fn frame_step(&mut self) -> Result<(), WaitError> {
+        match self.run(vec!["frame-step".into(), "1".into(), "seek".into()]) {
+            // Older versions reject the arguments.
+            Err(WaitError::Mpv(Error {
+                code: MpvError::InvalidParameter,
+                ..
+            })) => {}
+            res => return res,
+        }
+        self.ctx.set_property("pause".into(), Node::Flag(true))?;
+        let fps = self.float_property("container-fps")?;
+        if fps <= 0.0 {
+            let operation = Operation::GetProperty("container-fps".into());
+            return Err(Error::new(operation, MpvError::PropertyUnavailable).into());
+        }
+        self.seek(1.0 / fps, SeekMode::Relative, SeekPrecision::Exact)
+    }
+
+    /// Show the previous frame and pause ("frame-back-step"). This is an exact
+    /// seek, and slow.
+    pub fn frame_back_step(&mut self) -> Result<(), WaitError> {
+        self.run(vec!["frame-back-step".into()])
+    }
+
+    fn run(&mut self, args: Vec<String>) -> Result<(), WaitError> {
+        let operation = Operation::Command(args[0].clone());
+        let deadline = deadline(self.timeout);
+        self.command(args, deadline)?;
+        self.wait(
+            &operation,
+            |e| matches!(e, Event::PlaybackRestart),
+            time_left(deadline),
+        )
+    }
+
+    /// Run a command, and wait for its reply. Events before the reply belong
+    /// to earlier commands, and are skipped.
+    fn command(&mut self, args: Vec<String>, deadline: Option<Instant>) -> Result<(), WaitError> {
+        let operation = Operation::Command(args[0].clone());
+        self.last_id += 1;
+        let id = self.last_id;
+        let arg = Node::Array(args.into_iter().map(Node::String).collect());
+        self.ctx.command_node_async(arg, id)?;
+        let reply = self
+            .ctx
+            .wait_for(
+                |e| matches!(e, Event::CommandReply { reply_userdata, .. } if *reply_userdata == id),
+                time_left(deadline),
+            )
+            .map_err(|err| err.during(operation.clone()))?;
+        match reply {
+            Event::CommandReply {
+                result: Err(err), ..
+            } => Err(Error::new(operation, err).into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn float_property(&mut self, name: &str) -> Result<f64, Error> {
+        let node = self.ctx.get_property(name.into())?;
+        time_from_node(node).ok_or(Error::new(
+            Operation::GetProperty(name.into()),
+            MpvError::PropertyNotSupported,
+        ))
+    }
+
+    /// Wait for an event matching `pred`. The end of the file ends the wait
+    /// with an error.
+    fn wait(
+        &mut self,
+        operation: &Operation,
+        pred: fn(&Event) -> bool,
+        timeout: f64,
+    ) -> Result<(), WaitError> {
+        let event = self
+            .ctx
+            .wait_for(|e| pred(e) || matches!(e, Event::EndFile { .. }), timeout)
+            .map_err(|err| err.during(operation.clone()))?;
+        match event {
+            Event::EndFile {
+                reason: EndFileReason::Error(err),
+                ..
+            } => Err(Error::new(operation.clone(), err).into()),
+            Event::EndFile { .. } => {
+                Err(Error::new(operation.clone(), MpvError::NothingToPlay).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn time_or_no(time: Option<f64>) -> Node {
+    time.map_or(Node::from("no"), Node::Float64)
+}
+
+fn time_from_node(node: Node) -> Option<f64> {
+    match node {
+        Node::Float64(time) => Some(time),
+        Node::Int64(time) => Some(time as f64),
+        _ => None,
+    }
+}
+
+impl MpvHandle {
+    /// Loop between `a` and `b` seconds ("ab-loop-a", "ab-loop-b"). With only
+    /// `a`, playback loops from the end of the file to `a`; `None` for both
+    /// disables the loop.
+    pub fn set_ab_loop(&mut self, a: Option<f64>, b: Option<f64>) -> Result<(), Error> {
+        self.set_property("ab-loop-a".into(), time_or_no(a))?;
+        self.set_property("ab-loop-b".into(), time_or_no(b))
+    }
+
+    /// The A and B points of the loop, if set.
+    pub fn ab_loop(&mut self) -> Result<(Option<f64>, Option<f64>), Error> {
+        let a = self.get_property("ab-loop-a".into())?;
+        let b = self.get_property("ab-loop-b".into())?;
+        Ok((time_from_node(a), time_from_node(b)))
+    }
+
+    /// How often to loop before continuing after B ("ab-loop-count"). `None`
+    /// loops forever.
+    pub fn set_ab_loop_count(&mut self, count: Option<u32>) -> Result<(), Error> {
+        let value = count.map_or(Node::from("inf"), |count| Node::Int64(count.into()));
+        self.set_property("ab-loop-count".into(), value)
+    }
+}
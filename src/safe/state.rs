@@ -0,0 +1,117 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{
+    error::Error,
+    event::Event,
+    node::{MpvFormat, Node, Property, PropertyValue},
+    player::Player,
+};
+
+/// The latest values of a set of properties, kept up to date by observing
+/// them, so they can be read without asking the player.
+///
+/// Pass every event to `handle_event()`. All properties are observed with the
+/// same `reply_userdata`.
+#[derive(Debug, Clone)]
+pub struct PlayerState {
+    reply_userdata: u64,
+    /// Observed properties, with their value if available.
+    values: HashMap<String, Option<Node>>,
+    changed: BTreeSet<String>,
+}
+
+impl PlayerState {
+    pub fn new(reply_userdata: u64) -> Self {
+        Self {
+            reply_userdata,
+            values: HashMap::new(),
+            changed: BTreeSet::new(),
+        }
+    }
+
+    /// Observe a property in the given format. The value is unavailable until
+    /// the first change event arrives, which mpv sends right away.
+    pub fn observe(
+        &mut self,
+        ctx: &mut impl Player,
+        name: &str,
+        format: MpvFormat,
+    ) -> Result<(), Error> {
+        ctx.observe_property(name.into(), format, self.reply_userdata)?;
+        self.values.entry(name.into()).or_insert(None);
+        Ok(())
+    }
+
+    /// Observe a property in the format that `T` converts from.
+    pub fn observe_as<T: PropertyValue>(
+        &mut self,
+        ctx: &mut impl Player,
+        name: &str,
+    ) -> Result<(), Error> {
+        self.observe(ctx, name, T::FORMAT)
+    }
+
+    /// Stop observing all properties, and forget their values.
+    pub fn clear(&mut self, ctx: &mut impl Player) -> Result<(), Error> {
+        ctx.unobserve_property(self.reply_userdata)?;
+        self.values.clear();
+        self.changed.clear();
+        Ok(())
+    }
+
+    /// Update a value from a property change event. Returns the name of the
+    /// property if its value changed.
+    pub fn handle_event<'a>(&mut self, event: &'a Event) -> Option<&'a str> {
+        let Event::PropertyChange {
+            result: Ok(Some(Property { name, data })),
+            reply_userdata,
+        } = event
+        else {
+            return None;
+        };
+        if *reply_userdata != self.reply_userdata {
+            return None;
+        }
+        let value = self.values.get_mut(name)?;
+        if value == data {
+            return None;
+        }
+        value.clone_from(data);
+        self.changed.insert(name.clone());
+        Some(name)
+    }
+
+    /// The value of a property, converted to `T`. `None` if the property isn't
+    /// observed or available, or has another type.
+    pub fn get<T: PropertyValue>(&self, name: &str) -> Option<T> {
+        self.node(name).and_then(T::from_node)
+    }
+
+    pub fn node(&self, name: &str) -> Option<&Node> {
+        self.values.get(name)?.as_ref()
+    }
+
+    /// The names of the observed properties.
+    pub fn properties(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// A copy of all available values.
+    pub fn snapshot(&self) -> HashMap<String, Node> {
+        self.values
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), value.clone()?)))
+            .collect()
+    }
+
+    /// Whether a property changed since the last `take_changes()`.
+    pub fn is_changed(&self, name: &str) -> bool {
+        self.changed.contains(name)
+    }
+
+    /// The properties that changed since the last call, e.g. since the last
+    /// frame was drawn, sorted by name.
+    pub fn take_changes(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.changed).into_iter().collect()
+    }
+}
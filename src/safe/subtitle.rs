@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use super::{
+    client::MpvHandle,
+    error::{Error, MpvError},
+    event::Event,
+    node::{MpvFormat, Node, Property},
+};
+
+/// What "sub-add" does with the new track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubtitleFlags {
+    /// Select the track right away.
+    #[default]
+    Select,
+    /// Don't select the track, unless it's the only one.
+    Auto,
+    /// Select an already added track with the same URL instead of adding it
+    /// again, or add it if there is none.
+    Cached,
+}
+
+impl SubtitleFlags {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Select => "select",
+            Self::Auto => "auto",
+            Self::Cached => "cached",
+        }
+    }
+}
+
+/// A subtitle line, as currently shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleLine {
+    /// The text without formatting ("sub-text").
+    pub text: String,
+    /// Time the line is shown, in seconds of playback time, with
+    /// "sub-delay" applied ("sub-start"). `None` if unknown.
+    pub start: Option<f64>,
+    /// Time the line is hidden ("sub-end").
+    pub end: Option<f64>,
+}
+
+impl MpvHandle {
+    /// Add a subtitle file or URL to the current file ("sub-add"). `title` and
+    /// `lang` are shown in the track list; mpv guesses them if not given.
+    pub fn add_subtitle(
+        &mut self,
+        url: &str,
+        flags: SubtitleFlags,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut args = HashMap::from([
+            ("name".to_owned(), Node::from("sub-add")),
+            ("url".to_owned(), Node::from(url)),
+            ("flags".to_owned(), Node::from(flags.as_str())),
+        ]);
+        if let Some(title) = title {
+            args.insert("title".into(), title.into());
+        }
+        if let Some(lang) = lang {
+            args.insert("lang".into(), lang.into());
+        }
+        self.command_node(Node::Map(args), false).map(|_| ())
+    }
+
+    /// Add subtitles from a string in any format mpv reads, e.g. SRT, WebVTT
+    /// or ASS, without writing a file. Uses the "memory://" protocol.
+    pub fn add_subtitle_text(
+        &mut self,
+        contents: &str,
+        flags: SubtitleFlags,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<(), Error> {
+        self.add_subtitle(&format!("memory://{contents}"), flags, title, lang)
+    }
+
+    /// Remove a subtitle track by ID, or the selected one with `None`
+    /// ("sub-remove").
+    pub fn remove_subtitle(&mut self, id: Option<i64>) -> Result<(), Error> {
+        let mut args = vec!["sub-remove".into()];
+        args.extend(id.map(|id| id.to_string()));
+        self.command(args, false).map(|_| ())
+    }
+
+    /// Reload a subtitle track from its file or URL, e.g. after editing it
+    /// ("sub-reload"). `None` reloads the selected track.
+    pub fn reload_subtitle(&mut self, id: Option<i64>) -> Result<(), Error> {
+        let mut args = vec!["sub-reload".into()];
+        args.extend(id.map(|id| id.to_string()));
+        self.command(args, false).map(|_| ())
+    }
+
+    /// Show subtitles `seconds` later; negative values show them earlier.
+    pub fn set_sub_delay(&mut self, seconds: f64) -> Result<(), Error> {
+        self.set_property("sub-delay".into(), Node::Float64(seconds))
+    }
+
+    /// Select a track to show in addition to the primary subtitles, usually at
+    /// the top of the screen. `None` disables secondary subtitles.
+    pub fn set_secondary_sid(&mut self, id: Option<i64>) -> Result<(), Error> {
+        let value = id.map_or(Node::from("no"), Node::Int64);
+        self.set_property("secondary-sid".into(), value)
+    }
+
+    /// The subtitle line shown right now, or `None` if there is none.
+    pub fn subtitle_text(&mut self) -> Result<Option<SubtitleLine>, Error> {
+        let text = match self.get_property_string("sub-text".into()) {
+            Ok(text) => text,
+            Err(err) if err.code == MpvError::PropertyUnavailable => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let mut time = |name: &str| match self.get_property(name.into()) {
+            Ok(Node::Float64(time)) => Some(time),
+            _ => None,
+        };
+        let (mut start, mut end) = (time("sub-start"), time("sub-end"));
+        // The properties are read one by one; drop timings that may belong to
+        // the next line.
+        if self.get_property_string("sub-text".into()).as_ref() != Ok(&text) {
+            (start, end) = (None, None);
+        }
+        Ok(Some(SubtitleLine { text, start, end }))
+    }
+}
+
+/// Tracks the subtitle line that is shown, by observing "sub-text".
+///
+/// Pass every event to `handle_event()`, which returns `true` when the line
+/// changed.
+#[derive(Debug, Clone)]
+pub struct SubtitleWatcher {
+    reply_userdata: u64,
+    current: Option<SubtitleLine>,
+}
+
+impl SubtitleWatcher {
+    /// Start observing, with `reply_userdata` for the property change events.
+    pub fn start(ctx: &mut MpvHandle, reply_userdata: u64) -> Result<Self, Error> {
+        ctx.observe_property("sub-text".into(), MpvFormat::String, reply_userdata)?;
+        Ok(Self {
+            reply_userdata,
+            current: None,
+        })
+    }
+
+    /// The line shown right now, as of the last event.
+    pub fn current(&self) -> Option<&SubtitleLine> {
+        self.current.as_ref()
+    }
+
+    /// Update the current line from a "sub-text" change. Returns whether the
+    /// line changed. Its timings are `None` if the player has moved on to
+    /// another line by the time they are read.
+    pub fn handle_event(&mut self, ctx: &mut MpvHandle, event: &Event) -> bool {
+        let Event::PropertyChange {
+            result: Ok(Some(Property { data, .. })),
+            reply_userdata,
+        } = event
+        else {
+            return false;
+        };
+        if *reply_userdata != self.reply_userdata {
+            return false;
+        }
+        let line = match data {
+            Some(Node::String(text)) if !text.is_empty() => {
+                // Timing isn't part of the event; it's read right away. The
+                // line may have changed since, so only keep the timing of the
+                // same text.
+                let (start, end) = match ctx.subtitle_text() {
+                    Ok(Some(line)) if line.text == *text => (line.start, line.end),
+                    _ => (None, None),
+                };
+                Some(SubtitleLine {
+                    text: text.clone(),
+                    start,
+                    end,
+                })
+            }
+            _ => None,
+        };
+        if line == self.current {
+            return false;
+        }
+        self.current = line;
+        true
+    }
+
+    /// Stop observing.
+    pub fn stop(self, ctx: &mut MpvHandle) -> Result<(), Error> {
+        ctx.unobserve_property(self.reply_userdata).map(|_| ())
+    }
+}
@@ -0,0 +1,256 @@
+use std::{fmt::Write, path::Path, time::Instant};
+
+use super::{
+    builder::{KeepOpen, MpvBuilder},
+    client::MpvHandle,
+    error::{Error, MpvError, Operation, WaitError},
+    event::{EndFileReason, Event},
+    node::Node,
+    player::Player,
+    screenshot::{Frame, ScreenshotMode},
+    util::{deadline, time_left},
+};
+
+/// A frame taken at a specific time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    /// Position of the frame in seconds.
+    pub time: f64,
+    pub frame: Frame,
+}
+
+/// Several thumbnails tiled into a single image, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteSheet {
+    /// The combined image.
+    pub image: Frame,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    /// Time of every tile, in tile order.
+    pub times: Vec<f64>,
+    /// Duration of the file, used as end time of the last tile.
+    pub duration: f64,
+}
+
+impl SpriteSheet {
+    /// Tile the thumbnails into a sheet with the given number of columns. All
+    /// thumbnails must have the same size and pixel format. Returns `None` if
+    /// they don't, or if there are no thumbnails.
+    pub fn new(thumbnails: &[Thumbnail], columns: u32, duration: f64) -> Option<Self> {
+        let first = &thumbnails.first()?.frame;
+        let columns = columns.clamp(1, thumbnails.len() as u32);
+        let rows = (thumbnails.len() as u32).div_ceil(columns);
+        let (tile_width, tile_height) = (first.width, first.height);
+        let stride = tile_width as usize * 4 * columns as usize;
+        let mut data = vec![0; stride * (tile_height * rows) as usize];
+        for (i, thumbnail) in thumbnails.iter().enumerate() {
+            let frame = &thumbnail.frame;
+            if (frame.width, frame.height) != (tile_width, tile_height)
+                || frame.format != first.format
+            {
+                return None;
+            }
+            let (x, y) = (i % columns as usize, i / columns as usize);
+            for row in 0..tile_height {
+                let start = (y * tile_height as usize + row as usize) * stride
+                    + x * tile_width as usize * 4;
+                let src = frame.row(row)?;
+                data[start..start + src.len()].copy_from_slice(src);
+            }
+        }
+        Some(Self {
+            image: Frame {
+                width: tile_width * columns,
+                height: tile_height * rows,
+                stride: stride as i64,
+                format: first.format.clone(),
+                data,
+            },
+            tile_width,
+            tile_height,
+            columns,
+            times: thumbnails.iter().map(|t| t.time).collect(),
+            duration,
+        })
+    }
+
+    /// Position of tile `index` in the sheet, as (x, y, width, height).
+    pub fn tile_rect(&self, index: usize) -> (u32, u32, u32, u32) {
+        let (x, y) = (index as u32 % self.columns, index as u32 / self.columns);
+        (
+            x * self.tile_width,
+            y * self.tile_height,
+            self.tile_width,
+            self.tile_height,
+        )
+    }
+
+    /// Build a WebVTT index for the sheet, as used by web players for seek bar
+    /// previews. `url` is where the sheet image will be served from. Every cue
+    /// lasts until the next tile's time.
+    pub fn webvtt(&self, url: &str) -> String {
+        let mut vtt = String::from("WEBVTT\n");
+        for (i, &start) in self.times.iter().enumerate() {
+            let end = self.times.get(i + 1).copied().unwrap_or(self.duration);
+            let (x, y, w, h) = self.tile_rect(i);
+            let _ = write!(
+                vtt,
+                "\n{} --> {}\n{url}#xywh={x},{y},{w},{h}\n",
+                vtt_timestamp(start),
+                vtt_timestamp(end.max(start)),
+            );
+        }
+        vtt
+    }
+}
+
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Extracts frames from media files with a dedicated headless mpv instance.
+///
+/// The file is opened paused, with audio and subtitles disabled, and every
+/// frame is found with a precise (hr-seek) seek.
+pub struct Thumbnailer {
+    ctx: MpvHandle,
+    duration: f64,
+    /// Seconds to wait for a file to open, or for a frame to be decoded.
+    /// Negative values wait forever.
+    pub timeout: f64,
+}
+
+impl Thumbnailer {
+    /// Create a thumbnailer, with a timeout of 10 seconds.
+    pub fn new() -> Result<Self, Vec<Error>> {
+        Self::with_builder(MpvBuilder::headless())
+    }
+
+    /// Create a thumbnailer from a custom builder, e.g. to set "hwdec". The
+    /// options needed for thumbnailing are added to it.
+    pub fn with_builder(builder: MpvBuilder) -> Result<Self, Vec<Error>> {
+        let ctx = builder
+            .option("pause", true)
+            .option("hr-seek", "yes")
+            .option("aid", "no")
+            .option("sid", "no")
+            .keep_open(KeepOpen::Always)
+            .build()?;
+        Ok(Self {
+            ctx,
+            duration: 0.0,
+            timeout: 10.0,
+        })
+    }
+
+    /// Scale frames to the given width, keeping the aspect ratio. `None` keeps
+    /// the video size.
+    pub fn set_width(&mut self, width: Option<u32>) -> Result<(), Error> {
+        let vf = match width {
+            Some(width) => format!("scale={width}:-2"),
+            None => String::new(),
+        };
+        self.ctx.set_property("vf".into(), Node::String(vf))
+    }
+
+    /// Open a file, and wait until it's ready for seeking. Fails with
+    /// `WaitError::Timeout` if that takes longer than `timeout`.
+    pub fn open(&mut self, path: &Path) -> Result<(), WaitError> {
+        let operation = Operation::Command("loadfile".into());
+        let path = path.to_string_lossy().into_owned();
+        self.ctx.command(vec!["loadfile".into(), path], false)?;
+        let deadline = deadline(self.timeout);
+        // Skip the end of a previously opened file.
+        self.ctx
+            .wait_for(
+                |e| matches!(e, Event::StartFile { .. }),
+                time_left(deadline),
+            )
+            .map_err(|err| err.during(operation.clone()))?;
+        self.wait_restart(operation, deadline)?;
+        self.duration = match self.ctx.get_property("duration".into()) {
+            Ok(Node::Float64(duration)) => duration,
+            _ => 0.0,
+        };
+        Ok(())
+    }
+
+    /// Duration of the open file in seconds, or 0 if it's unknown.
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Get the frame at `time` seconds. Fails with `WaitError::Timeout` if
+    /// decoding it takes longer than `timeout`.
+    pub fn frame_at(&mut self, time: f64) -> Result<Thumbnail, WaitError> {
+        let operation = Operation::Command("seek".into());
+        self.ctx.command(
+            vec!["seek".into(), time.to_string(), "absolute+exact".into()],
+            false,
+        )?;
+        self.wait_restart(operation, deadline(self.timeout))?;
+        let frame = self.ctx.screenshot_raw(ScreenshotMode::Video)?;
+        Ok(Thumbnail { time, frame })
+    }
+
+    /// Get the frames at all given times.
+    pub fn frames(&mut self, times: &[f64]) -> Result<Vec<Thumbnail>, WaitError> {
+        times.iter().map(|&time| self.frame_at(time)).collect()
+    }
+
+    /// Get a frame every `interval` seconds, starting at 0.
+    pub fn frames_every(&mut self, interval: f64) -> Result<Vec<Thumbnail>, WaitError> {
+        if interval <= 0.0 {
+            return Err(Error::new(
+                Operation::Command("seek".into()),
+                MpvError::InvalidParameter,
+            )
+            .into());
+        }
+        let count = (self.duration / interval).ceil().max(1.0) as usize;
+        let times = (0..count).map(|i| i as f64 * interval).collect::<Vec<_>>();
+        self.frames(&times)
+    }
+
+    /// Get the frames at all given times, tiled into a sprite sheet.
+    pub fn sprite_sheet(&mut self, times: &[f64], columns: u32) -> Result<SpriteSheet, WaitError> {
+        let thumbnails = self.frames(times)?;
+        let err = Error::new(
+            Operation::Command("screenshot-raw".into()),
+            MpvError::InvalidParameter,
+        );
+        SpriteSheet::new(&thumbnails, columns, self.duration).ok_or(err.into())
+    }
+
+    /// Wait until playback (re)starts after a load or seek. A load error ends
+    /// the wait with the error reported by mpv.
+    fn wait_restart(
+        &mut self,
+        operation: Operation,
+        deadline: Option<Instant>,
+    ) -> Result<(), WaitError> {
+        let event = self
+            .ctx
+            .wait_for(
+                |e| matches!(e, Event::PlaybackRestart | Event::EndFile { .. }),
+                time_left(deadline),
+            )
+            .map_err(|err| err.during(operation.clone()))?;
+        match event {
+            Event::EndFile {
+                reason: EndFileReason::Error(err),
+                ..
+            } => Err(Error::new(operation, err).into()),
+            Event::EndFile { .. } => Err(Error::new(operation, MpvError::NothingToPlay).into()),
+            _ => Ok(()),
+        }
+    }
+}
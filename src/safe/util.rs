@@ -1,11 +1,9 @@
-use std::ffi::{CStr, CString};
+use std::{
+    ffi::{CStr, CString},
+    time::{Duration, Instant},
+};
 
-pub fn make_rust_string(data: *mut i8) -> Option<String> {
-    match unsafe { CString::from_raw(data) }.into_string() {
-        Ok(data) => Some(data),
-        Err(_) => None,
-    }
-}
+use super::node::Node;
 
 pub fn make_rust_string_const(data: *const i8) -> Option<String> {
     match unsafe { CStr::from_ptr(data) }.to_owned().into_string() {
@@ -20,3 +18,59 @@ pub fn make_c_string(s: String) -> Option<*mut i8> {
     };
     Some(data.into_raw())
 }
+
+/// Quote a command argument for input.conf syntax, as used by mpv_command_string()
+/// and "define-section".
+pub fn quote_arg(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Extract the command name from the argument of mpv_command_node(): the first
+/// item of an array, or the "name" entry of a map.
+pub fn command_name(args: &Node) -> String {
+    let name = match args {
+        Node::Array(args) => args.first(),
+        Node::Map(args) => args.get("name"),
+        _ => None,
+    };
+    match name {
+        Some(Node::String(name)) => name.clone(),
+        _ => String::new(),
+    }
+}
+
+/// `timeout` seconds as a `Duration`, or `None` for waits that don't end:
+/// negative timeouts, and those too long for a `Duration` (including
+/// infinity and NaN).
+pub fn timeout_duration(timeout: f64) -> Option<Duration> {
+    if timeout < 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(timeout).ok()
+}
+
+/// When a wait of `timeout` seconds starting now ends, or `None` if it waits
+/// forever (see `timeout_duration()`).
+pub fn deadline(timeout: f64) -> Option<Instant> {
+    Instant::now().checked_add(timeout_duration(timeout)?)
+}
+
+/// The timeout to pass to `wait_for()` to wait until `deadline`.
+pub fn time_left(deadline: Option<Instant>) -> f64 {
+    deadline.map_or(-1.0, |deadline| {
+        deadline
+            .saturating_duration_since(Instant::now())
+            .as_secs_f64()
+    })
+}
@@ -0,0 +1,81 @@
+mod common;
+
+#[cfg(feature = "libmpv")]
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn tags_and_text() {
+    let ass = AssBuilder::new()
+        .pos(10.0, 20.5)
+        .align(7)
+        .color(0x11, 0x22, 0x33)
+        .alpha(0x80)
+        .font("Dejavu {Sans}")
+        .font_size(32.0)
+        .bold(true)
+        .border(1.5)
+        .text("a {b}\\N\nc")
+        .build();
+    assert_eq!(
+        ass,
+        "{\\pos(10,20.5)}{\\an7}{\\1c&H332211&}{\\alpha&H80&}{\\fnDejavu Sans}{\\fs32}{\\b1}\
+         {\\bord1.5}a \\{b}\\\u{2060}N\\Nc"
+    );
+    assert_eq!(ass_escape("{x}"), "\\{x}");
+    // Spaces in the text are kept, even at the end.
+    assert_eq!(AssBuilder::new().text("a  ").build(), "a  ");
+}
+
+#[test]
+fn drawing() {
+    let ass = AssBuilder::new()
+        .pos(0.0, 0.0)
+        .rect(0.0, 0.0, 10.0, 5.0)
+        .new_event()
+        .move_to(0.0, 0.0)
+        .bezier_to(1.0, 2.0, 3.0, 4.0, 5.0, 6.0)
+        .text("after")
+        .build();
+    assert_eq!(
+        ass,
+        "{\\pos(0,0)}{\\p1}m 0 0 l 10 0 l 10 5 l 0 5{\\p0}\n\
+         {\\p1}m 0 0 b 1 2 3 4 5 6{\\p0}after"
+    );
+}
+
+#[test]
+fn script() {
+    let script = AssBuilder::new()
+        .text("hello")
+        .build_script(640, 360, 0.0, 3661.5);
+    assert!(script.contains("PlayResX: 640\nPlayResY: 360\n"));
+    assert!(script.ends_with("Dialogue: 0,0:00:00.00,1:01:01.50,Default,,0,0,0,,hello\n"));
+    assert!(script.contains("\nStyle: Default,sans-serif,18,"));
+    // The font size stays positive for tiny scripts.
+    let tiny = AssBuilder::new().text("x").build_script(16, 10, 0.0, 1.0);
+    assert!(tiny.contains("\nStyle: Default,sans-serif,1,"));
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn with_player() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+
+    let mut overlay = OsdOverlay::new(&mut ctx).unwrap();
+    overlay.data = AssBuilder::new()
+        .color(255, 0, 0)
+        .rect(0.0, 0.0, 100.0, 100.0)
+        .build();
+    overlay.update().unwrap();
+
+    let script = AssBuilder::new()
+        .text("drawn from rust")
+        .build_script(64, 48, 0.0, 2.0);
+    ctx.sub_add_ass(&script, "rust").unwrap();
+    let Ok(Node::String(title)) = ctx.get_property("current-tracks/sub/title".into()) else {
+        panic!("no subtitle track");
+    };
+    assert_eq!(title, "rust");
+}
@@ -0,0 +1,186 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn create_and_initialize() {
+    let ctx = MpvHandle::for_tests();
+    assert!(!ctx.name().is_empty());
+    assert!(ctx.id() > 0);
+}
+
+#[test]
+fn builder_reports_every_failed_option() {
+    let errors = MpvBuilder::headless()
+        .option("no-such-option-1", "x")
+        .option("no-such-option-2", "y")
+        .build()
+        .err()
+        .expect("unknown options must fail");
+    assert_eq!(
+        errors,
+        vec![
+            Error::new(
+                Operation::SetOption("no-such-option-1".into()),
+                MpvError::OptionNotFound
+            ),
+            Error::new(
+                Operation::SetOption("no-such-option-2".into()),
+                MpvError::OptionNotFound
+            ),
+        ]
+    );
+}
+
+#[test]
+fn command_without_result() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+    let res = ctx.command(vec!["seek".into(), "1".into(), "absolute".into()], false);
+    assert_eq!(res, Ok(None));
+}
+
+#[test]
+fn command_with_result() {
+    let mut ctx = MpvHandle::for_tests();
+    let res = ctx.command(vec!["expand-text".into(), "a${=pause}b".into()], true);
+    assert_eq!(res, Ok(Some(Node::String("anob".into()))));
+}
+
+#[test]
+fn command_error_carries_the_command_name() {
+    let mut ctx = MpvHandle::for_tests();
+    let err = ctx
+        .command(vec!["no-such-command".into()], false)
+        .unwrap_err();
+    assert_eq!(err.operation, Operation::Command("no-such-command".into()));
+    assert!(err.to_string().contains("no-such-command"));
+}
+
+#[test]
+fn command_node_array() {
+    let mut ctx = MpvHandle::for_tests();
+    let res = ctx.command_node(
+        Node::Array(vec!["expand-text".into(), "${=idle-active}".into()]),
+        true,
+    );
+    assert_eq!(res, Ok(Some(Node::String("yes".into()))));
+}
+
+#[test]
+fn command_node_map() {
+    let mut ctx = MpvHandle::for_tests();
+    let args = HashMap::from([
+        ("name".to_string(), Node::from("expand-text")),
+        ("text".to_string(), Node::from("hello")),
+    ]);
+    let res = ctx.command_node(Node::Map(args), true);
+    assert_eq!(res, Ok(Some(Node::String("hello".into()))));
+}
+
+#[test]
+fn property_round_trip() {
+    let mut ctx = MpvHandle::for_tests();
+
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+    assert_eq!(ctx.get_property("pause".into()), Ok(Node::Flag(true)));
+
+    ctx.set_property("volume".into(), Node::Float64(42.5))
+        .unwrap();
+    assert_eq!(ctx.get_property("volume".into()), Ok(Node::Float64(42.5)));
+
+    ctx.set_property("loop-file".into(), Node::Int64(3))
+        .unwrap();
+    assert_eq!(ctx.get_property("loop-file".into()), Ok(Node::Int64(3)));
+
+    ctx.set_property("title".into(), Node::from("some title"))
+        .unwrap();
+    assert_eq!(
+        ctx.get_property("title".into()),
+        Ok(Node::String("some title".into()))
+    );
+}
+
+#[test]
+fn property_errors() {
+    let mut ctx = MpvHandle::for_tests();
+    let err = ctx.get_property("no-such-property".into()).unwrap_err();
+    assert_eq!(err.code, MpvError::PropertyNotFound);
+    assert_eq!(
+        err.operation,
+        Operation::GetProperty("no-such-property".into())
+    );
+
+    // Nothing is playing, so there is no duration.
+    let err = ctx.get_property("duration".into()).unwrap_err();
+    assert_eq!(err.code, MpvError::PropertyUnavailable);
+}
+
+#[test]
+fn observe_property() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.observe_property("pause".into(), MpvFormat::Flag, 7)
+        .unwrap();
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+
+    let event = wait_for(&mut ctx, |e| {
+        matches!(
+            e,
+            Event::PropertyChange {
+                result: Ok(Some(Property {
+                    data: Some(Node::Flag(true)),
+                    ..
+                })),
+                ..
+            }
+        )
+    });
+    let Event::PropertyChange {
+        reply_userdata,
+        result,
+    } = event
+    else {
+        unreachable!();
+    };
+    assert_eq!(reply_userdata, 7);
+    assert_eq!(result.unwrap().unwrap().name, "pause");
+
+    assert_eq!(ctx.unobserve_property(7), Ok(1));
+}
+
+#[test]
+fn observe_property_as_node() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.observe_property("time-pos".into(), MpvFormat::Node, 1)
+        .unwrap();
+    loadfile(&mut ctx, VIDEO);
+    wait_for(&mut ctx, |e| {
+        matches!(
+            e,
+            Event::PropertyChange {
+                result: Ok(Some(Property {
+                    data: Some(Node::Float64(_)),
+                    ..
+                })),
+                ..
+            }
+        )
+    });
+}
+
+#[test]
+fn error_strings_match_libmpv() {
+    use libmpv_rs::raw::*;
+
+    for code in mpv_error_MPV_ERROR_GENERIC..mpv_error_MPV_ERROR_SUCCESS {
+        let raw = unsafe { std::ffi::CStr::from_ptr(mpv_error_string(code)) };
+        let raw = raw.to_str().unwrap();
+        let err = MpvError::from_error_string(raw).unwrap();
+        assert_eq!(err.to_string(), raw);
+    }
+}
@@ -0,0 +1,72 @@
+//! Shared helpers for the integration tests. They run against the real libmpv,
+//! with null audio and video outputs, and use lavfi sources instead of media
+//! files.
+
+#![allow(dead_code)]
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "libmpv")]
+use libmpv_rs::safe::*;
+
+/// Two seconds of a 64x48 test pattern at 10 fps.
+pub const VIDEO: &str = "av://lavfi:testsrc=duration=2:size=64x48:rate=10";
+
+/// Half a second of the test pattern, for tests that play until the end.
+pub const SHORT_VIDEO: &str = "av://lavfi:testsrc=duration=0.5:size=64x48:rate=10";
+
+/// Two seconds of a sine wave.
+pub const AUDIO: &str = "av://lavfi:sine=duration=2";
+
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[cfg(feature = "libmpv")]
+pub fn loadfile(ctx: &mut MpvHandle, url: &str) {
+    ctx.command(vec!["loadfile".into(), url.into()], false)
+        .expect("loadfile failed");
+}
+
+#[cfg(feature = "libmpv")]
+/// Wait until an event matching `pred` arrives, and return it. Panics after
+/// `TIMEOUT`, or if a file fails to load.
+pub fn wait_for(ctx: &mut MpvHandle, pred: impl FnMut(&Event) -> bool) -> Event {
+    Player::wait_for(ctx, pred, TIMEOUT.as_secs_f64()).expect("waiting for event failed")
+}
+
+#[cfg(feature = "libmpv")]
+/// Load `url` and wait until it is playing.
+pub fn play(ctx: &mut MpvHandle, url: &str) {
+    loadfile(ctx, url);
+    wait_for(ctx, |e| matches!(e, Event::FileLoaded));
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run a future to completion on the current thread, parking it while the
+/// future is pending. Panics after `TIMEOUT`.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        let left = deadline.checked_duration_since(Instant::now());
+        thread::park_timeout(left.expect("timed out waiting for future"));
+    }
+}
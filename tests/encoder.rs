@@ -0,0 +1,72 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::*;
+use libmpv_rs::safe::*;
+
+fn output(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("libmpv-rs-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn encode_clip() {
+    let path = output("clip.nut");
+    let mut updates = vec![];
+    Encoder::new(&path)
+        .input(VIDEO)
+        .format("nut")
+        .video_codec("rawvideo")
+        .start(0.5)
+        .end(1.0)
+        .run(|progress| updates.push(*progress))
+        .unwrap();
+
+    assert!(fs::metadata(&path).unwrap().len() > 0);
+    assert!(!updates.is_empty());
+    assert!(updates.iter().all(|p| p.input == 0));
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn encode_several_inputs() {
+    let path = output("concat.nut");
+    let mut inputs = vec![];
+    Encoder::new(&path)
+        .input(SHORT_VIDEO)
+        .input(SHORT_VIDEO)
+        .format("nut")
+        .video_codec("rawvideo")
+        .run(|progress| inputs.push(progress.input))
+        .unwrap();
+
+    assert!(inputs.contains(&1));
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn missing_input() {
+    let path = output("missing.nut");
+    let err = Encoder::new(&path)
+        .input("/nonexistent/file.mkv")
+        .format("nut")
+        .run(|_| {})
+        .unwrap_err();
+    assert!(
+        matches!(&err, EncodeError::Input { path, .. } if path == "/nonexistent/file.mkv"),
+        "{err}"
+    );
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn invalid_option() {
+    let err = Encoder::new(output("invalid.nut"))
+        .input(VIDEO)
+        .option("no-such-option", true)
+        .run(|_| {})
+        .unwrap_err();
+    assert!(matches!(err, EncodeError::Setup(_)));
+}
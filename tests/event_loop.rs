@@ -0,0 +1,102 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc,
+};
+
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn dispatches_by_kind() {
+    let mut event_loop = EventLoop::new(MpvHandle::for_tests());
+    let (tx, rx) = mpsc::channel();
+
+    let sender = tx.clone();
+    event_loop.on_file_loaded(move |_| sender.send("loaded".to_owned()).unwrap());
+    let sender = tx.clone();
+    event_loop.on_end_file(move |ctx, reason| {
+        sender.send(format!("end {reason:?}")).unwrap();
+        ctx.command(vec!["quit".into()], false).unwrap();
+    });
+    let sender = tx.clone();
+    event_loop.on_client_message(move |_, args| sender.send(args.join(" ")).unwrap());
+    let sender = tx.clone();
+    event_loop
+        .on_property::<bool>("pause", move |ctx, pause| {
+            if pause == Some(false) {
+                ctx.command(
+                    vec!["script-message".into(), "hello".into(), "world".into()],
+                    false,
+                )
+                .unwrap();
+            }
+            sender.send(format!("pause {pause:?}")).unwrap();
+        })
+        .unwrap();
+    let hooked = Arc::new(AtomicBool::new(false));
+    let flag = hooked.clone();
+    event_loop
+        .on_hook("on_load", 0, move |_| flag.store(true, Ordering::SeqCst))
+        .unwrap();
+
+    loadfile(event_loop.handle(), SHORT_VIDEO);
+    let event_loop = event_loop.spawn().join().unwrap();
+    assert!(event_loop.is_shut_down());
+    assert!(hooked.load(Ordering::SeqCst));
+    drop(tx);
+    let messages: Vec<_> = rx.iter().collect();
+    assert_eq!(messages[0], "pause Some(false)");
+    assert!(messages.contains(&"hello world".to_owned()));
+    let loaded = messages.iter().position(|m| m == "loaded").unwrap();
+    let end = messages.iter().position(|m| m == "end EOF").unwrap();
+    assert!(loaded < end);
+}
+
+#[test]
+fn run_pending() {
+    let mut event_loop = EventLoop::new(MpvHandle::for_tests());
+    let (tx, rx) = mpsc::channel();
+    let id = event_loop
+        .on_property::<f64>("volume", move |_, volume| tx.send(volume).unwrap())
+        .unwrap();
+    event_loop.run_once(TIMEOUT.as_secs_f64());
+    assert_eq!(rx.try_recv(), Ok(Some(100.0)));
+
+    event_loop
+        .handle()
+        .set_property("volume".into(), Node::Float64(50.0))
+        .unwrap();
+    event_loop.run_once(TIMEOUT.as_secs_f64());
+    assert_eq!(rx.try_recv(), Ok(Some(50.0)));
+
+    event_loop.remove_property(id).unwrap();
+    event_loop
+        .handle()
+        .set_property("volume".into(), Node::Float64(60.0))
+        .unwrap();
+    assert!(event_loop.run_pending());
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn panicking_hook_continues() {
+    let mut event_loop = EventLoop::new(MpvHandle::for_tests());
+    event_loop
+        .on_hook("on_load", 0, |_| panic!("hook failed"))
+        .unwrap();
+    let loaded = Arc::new(AtomicBool::new(false));
+    let flag = loaded.clone();
+    event_loop.on_file_loaded(move |_| flag.store(true, Ordering::SeqCst));
+
+    loadfile(event_loop.handle(), VIDEO);
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| event_loop.run()));
+    assert!(res.is_err());
+    // The player wasn't left waiting for the hook.
+    while !loaded.load(Ordering::SeqCst) {
+        assert!(event_loop.run_once(TIMEOUT.as_secs_f64()));
+    }
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::{thread, time::Duration};
+
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn events_arrive_on_channel() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut events = ctx.create_client(c"events").unwrap();
+    events
+        .observe_property("pause".into(), MpvFormat::Flag, 1)
+        .unwrap();
+    let rx = events.spawn_event_thread(16);
+
+    loadfile(&mut ctx, VIDEO);
+    loop {
+        match rx.recv_timeout(TIMEOUT).unwrap() {
+            StreamEvent::Event(Event::FileLoaded) => break,
+            StreamEvent::Dropped(_) => panic!("unexpected drop"),
+            _ => {}
+        }
+    }
+
+    // Termination waits for the event thread, which may wait for room for the
+    // shutdown event.
+    let terminate = thread::spawn(move || ctx.terminate());
+    let last = rx.iter().last();
+    assert_eq!(last, Some(StreamEvent::Event(Event::Shutdown)));
+    terminate.join().unwrap();
+    rx.join().unwrap();
+}
+
+#[test]
+fn full_channel_resyncs() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut events = ctx.create_client(c"events").unwrap();
+    events
+        .observe_property("volume".into(), MpvFormat::Float64, 1)
+        .unwrap();
+    let rx = events.spawn_event_thread(1);
+
+    for volume in 0..20 {
+        ctx.set_property("volume".into(), Node::Float64(volume.into()))
+            .unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    let first = rx.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(
+        first,
+        StreamEvent::Event(Event::PropertyChange { .. })
+    ));
+    // Reading made room for the dropped count.
+    let event = rx.recv_timeout(TIMEOUT).unwrap();
+    assert!(matches!(event, StreamEvent::Dropped(n) if n > 0));
+}
+
+#[test]
+fn full_channel_keeps_hooks() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut events = ctx.create_client(c"events").unwrap();
+    events.hook_add("on_load", 0, 5).unwrap();
+    let rx = events.spawn_event_thread(1);
+
+    // Fill the channel, so the thread drops events.
+    for i in 0..100 {
+        ctx.command(vec!["script-message".into(), i.to_string()], false)
+            .unwrap();
+    }
+    loadfile(&mut ctx, VIDEO);
+    let mut dropped = false;
+    let id = loop {
+        match rx.recv_timeout(TIMEOUT).unwrap() {
+            StreamEvent::Event(Event::Hook { id, .. }) => break id,
+            StreamEvent::Dropped(_) => dropped = true,
+            _ => {}
+        }
+    };
+    assert!(dropped);
+    // The file only loads once the hook is continued.
+    rx.hook_continue(id).unwrap();
+    wait_for(&mut ctx, |e| matches!(e, Event::FileLoaded));
+
+    drop(rx);
+    ctx.terminate();
+}
+
+#[test]
+fn join_stops_idle_thread() {
+    let mut ctx = MpvHandle::for_tests();
+    let events = ctx.create_client(c"events").unwrap();
+    let rx = events.spawn_event_thread(16);
+    // The thread is woken from waiting for events, and its handle destroyed,
+    // so the player terminates.
+    rx.join().unwrap();
+    ctx.terminate();
+}
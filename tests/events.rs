@@ -0,0 +1,166 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+fn wait_for_end_file(ctx: &mut MpvHandle) -> (EndFileReason, i64) {
+    match wait_for(ctx, |e| matches!(e, Event::EndFile { .. })) {
+        Event::EndFile {
+            reason,
+            playlist_entry_id,
+            ..
+        } => (reason, playlist_entry_id),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn wait_event_times_out() {
+    let mut ctx = MpvHandle::for_tests();
+    // Drain whatever initialization produced, then poll an idle player.
+    while ctx.wait_event(0.0).is_some() {}
+    assert_eq!(ctx.wait_event(0.0), None);
+}
+
+#[test]
+fn playback_events_in_order() {
+    let mut ctx = MpvHandle::for_tests();
+    loadfile(&mut ctx, VIDEO);
+    let start = wait_for(&mut ctx, |e| matches!(e, Event::StartFile { .. }));
+    let Event::StartFile { playlist_entry_id } = start else {
+        unreachable!();
+    };
+    wait_for(&mut ctx, |e| matches!(e, Event::FileLoaded));
+    wait_for(&mut ctx, |e| matches!(e, Event::PlaybackRestart));
+
+    ctx.command(vec!["stop".into()], false).unwrap();
+    let (reason, id) = wait_for_end_file(&mut ctx);
+    assert_eq!(reason, EndFileReason::Stop);
+    assert_eq!(id, playlist_entry_id);
+}
+
+#[test]
+fn end_file_eof() {
+    let mut ctx = MpvHandle::for_tests();
+    loadfile(&mut ctx, SHORT_VIDEO);
+    let (reason, _) = wait_for_end_file(&mut ctx);
+    assert_eq!(reason, EndFileReason::EOF);
+}
+
+#[test]
+fn end_file_eof_audio() {
+    let mut ctx = MpvHandle::for_tests();
+    loadfile(&mut ctx, "av://lavfi:sine=duration=0.2");
+    let (reason, _) = wait_for_end_file(&mut ctx);
+    assert_eq!(reason, EndFileReason::EOF);
+}
+
+#[test]
+fn end_file_error() {
+    let mut ctx = MpvHandle::for_tests();
+    loadfile(&mut ctx, "/nonexistent/file.mkv");
+    let (reason, _) = wait_for_end_file(&mut ctx);
+    assert!(matches!(reason, EndFileReason::Error(_)), "{reason:?}");
+}
+
+#[test]
+fn seek_events() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+    wait_for(&mut ctx, |e| matches!(e, Event::PlaybackRestart));
+    ctx.command(vec!["seek".into(), "1".into(), "absolute".into()], false)
+        .unwrap();
+    wait_for(&mut ctx, |e| matches!(e, Event::Seek));
+    wait_for(&mut ctx, |e| matches!(e, Event::PlaybackRestart));
+}
+
+#[test]
+fn client_message() {
+    let mut ctx = MpvHandle::for_tests();
+    let name = ctx.name();
+    ctx.command(
+        vec![
+            "script-message-to".into(),
+            name,
+            "hello".into(),
+            "world".into(),
+        ],
+        false,
+    )
+    .unwrap();
+    let event = wait_for(&mut ctx, |e| matches!(e, Event::ClientMessage { .. }));
+    assert_eq!(
+        event,
+        Event::ClientMessage {
+            args: vec!["hello".into(), "world".into()]
+        }
+    );
+}
+
+#[test]
+fn shutdown() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.command(vec!["quit".into()], false).unwrap();
+    wait_for(&mut ctx, |e| matches!(e, Event::Shutdown));
+}
+
+#[test]
+fn event_names() {
+    assert_eq!(Event::FileLoaded.get_event_string(), "file-loaded");
+    assert_eq!(Event::Shutdown.get_event_string(), "shutdown");
+}
+
+#[test]
+fn load_and_wait() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.load_and_wait(VIDEO).unwrap();
+    // Replacing a playing file skips its end.
+    ctx.load_and_wait(AUDIO).unwrap();
+
+    let err = ctx.load_and_wait("av://lavfi:nonexistent").unwrap_err();
+    assert_eq!(err.operation, Operation::Command("loadfile".into()));
+    ctx.load_and_wait(SHORT_VIDEO).unwrap();
+}
+
+#[test]
+fn wait_for_surfaces_errors() {
+    let mut ctx = MpvHandle::for_tests();
+    let err = ctx
+        .wait_for(|e| matches!(e, Event::FileLoaded), 0.2)
+        .unwrap_err();
+    assert_eq!(err, WaitError::Timeout(Operation::WaitEvent));
+
+    loadfile(&mut ctx, "av://lavfi:nonexistent");
+    let err = ctx
+        .wait_for(|e| matches!(e, Event::PlaybackRestart), -1.0)
+        .unwrap_err();
+    assert_eq!(err.operation(), &Operation::WaitEvent);
+
+    loadfile(&mut ctx, SHORT_VIDEO);
+    let event = ctx
+        .wait_for(
+            |e| matches!(e, Event::EndFile { .. }),
+            TIMEOUT.as_secs_f64(),
+        )
+        .unwrap();
+    assert!(matches!(
+        event,
+        Event::EndFile {
+            reason: EndFileReason::EOF,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn async_waits() {
+    let mut ctx = MpvHandle::for_tests();
+    block_on(ctx.load_and_wait_async(VIDEO)).unwrap();
+    ctx.command(vec!["seek".into(), "1".into()], false).unwrap();
+    block_on(ctx.wait_for_async(|e| matches!(e, Event::PlaybackRestart))).unwrap();
+
+    let err = block_on(ctx.load_and_wait_async("av://lavfi:nonexistent")).unwrap_err();
+    assert_eq!(err.operation, Operation::Command("loadfile".into()));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use std::collections::HashMap;
+
+use libmpv_rs::safe::*;
+
+#[test]
+fn syntax() {
+    let filter = Filter::new("crop").label("c").arg(100).arg(50);
+    assert_eq!(filter.to_string(), "@c:crop=100:50");
+
+    let filter = Filter::new("scale")
+        .param("w", 640)
+        .param("h", -2)
+        .enabled(false);
+    assert_eq!(filter.to_string(), "!scale=w=640:h=-2");
+
+    let graph = "hflip,eq=contrast=1.2";
+    let filter = Filter::lavfi(graph).label("g");
+    assert_eq!(filter.to_string(), format!("@g:lavfi=graph=%21%{graph}"));
+
+    let chain = FilterChain::new()
+        .push(Filter::new("hflip"))
+        .push(Filter::new("eq").param("gamma", ""));
+    assert_eq!(chain.to_string(), "hflip,eq=gamma=%0%");
+}
+
+#[test]
+fn from_node() {
+    let node = Node::Array(vec![
+        Node::Map(HashMap::from([
+            ("name".to_owned(), Node::from("eq")),
+            ("label".to_owned(), Node::from("e")),
+            ("enabled".to_owned(), Node::Flag(false)),
+            (
+                "params".to_owned(),
+                Node::Map(HashMap::from([
+                    ("gamma".to_owned(), Node::from("2")),
+                    ("contrast".to_owned(), Node::from("1.5")),
+                ])),
+            ),
+        ])),
+        Node::from("not a filter"),
+        Node::Map(HashMap::from([("name".to_owned(), Node::from("hflip"))])),
+    ]);
+    let chain = FilterChain::from_node(&node).unwrap();
+    assert_eq!(
+        chain,
+        FilterChain(vec![
+            Filter::new("eq")
+                .label("e")
+                .enabled(false)
+                .param("contrast", "1.5")
+                .param("gamma", "2"),
+            Filter::new("hflip"),
+        ])
+    );
+    assert_eq!(chain.get("e").map(|f| f.name.as_str()), Some("eq"));
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn runtime_changes() {
+    let mut ctx = MpvHandle::for_tests();
+    let eq = Filter::new("eq").label("eq").param("brightness", 0.25);
+    let flip = Filter::new("hflip").label("flip");
+    ctx.set_filters(FilterType::Video, &FilterChain::new().push(eq.clone()))
+        .unwrap();
+    ctx.add_filter(FilterType::Video, &flip).unwrap();
+
+    let chain = ctx.filters(FilterType::Video).unwrap();
+    assert_eq!(chain.0.len(), 2);
+    let found = chain.get("eq").unwrap();
+    assert_eq!(found.name, "eq");
+    assert!(found.enabled);
+    assert_eq!(
+        found.params,
+        vec![(Some("brightness".into()), "0.25".into())]
+    );
+
+    ctx.toggle_filter(FilterType::Video, &flip).unwrap();
+    let chain = ctx.filters(FilterType::Video).unwrap();
+    assert!(chain.get("flip").is_none_or(|f| !f.enabled));
+
+    ctx.remove_filter(FilterType::Video, &eq).unwrap();
+    assert!(ctx.filters(FilterType::Video).unwrap().get("eq").is_none());
+
+    ctx.add_filter(FilterType::Audio, &Filter::new("volume").arg(0.5))
+        .unwrap();
+    assert_eq!(ctx.filters(FilterType::Audio).unwrap().0.len(), 1);
+    ctx.clear_filters(FilterType::Audio).unwrap();
+    assert_eq!(ctx.filters(FilterType::Audio).unwrap(), FilterChain::new());
+
+    let err = ctx
+        .add_filter(FilterType::Video, &Filter::new("no-such-filter"))
+        .unwrap_err();
+    assert_eq!(err.operation, Operation::Command("vf".into()));
+}
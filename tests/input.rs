@@ -0,0 +1,116 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::{cell::Cell, rc::Rc};
+
+use common::*;
+use libmpv_rs::safe::*;
+
+fn client_message(event: &Event) -> bool {
+    matches!(event, Event::ClientMessage { .. })
+}
+
+#[test]
+fn section_closures() {
+    let mut ctx = MpvHandle::for_tests();
+    let pressed = Rc::new(Cell::new(0));
+    let mut section = {
+        let pressed = pressed.clone();
+        InputSection::new("test-keys")
+            .mode(SectionMode::Force)
+            .bind("x", move |ctx| {
+                pressed.set(pressed.get() + 1);
+                ctx.set_property("user-data/x".into(), Node::Flag(true))
+                    .unwrap();
+            })
+            .unwrap()
+            .bind_command("y", "script-message from-command \"a b\"")
+            .unwrap()
+    };
+    section.define(&mut ctx).unwrap();
+
+    // Not enabled yet.
+    ctx.keypress("x").unwrap();
+    ctx.command(vec!["script-message".into(), "marker".into()], false)
+        .unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert_eq!(
+        event,
+        Event::ClientMessage {
+            args: vec!["marker".into()]
+        }
+    );
+    assert_eq!(pressed.get(), 0);
+
+    section.enable(&mut ctx, EnableFlags::default()).unwrap();
+    ctx.keypress("y").unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert_eq!(
+        event,
+        Event::ClientMessage {
+            args: vec!["from-command".into(), "a b".into()]
+        }
+    );
+    assert!(!section.handle_event(&mut ctx, &event));
+
+    ctx.keypress("x").unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert!(section.handle_event(&mut ctx, &event));
+    assert_eq!(pressed.get(), 1);
+    assert_eq!(ctx.get_property("user-data/x".into()), Ok(Node::Flag(true)));
+
+    ctx.keydown("x").unwrap();
+    ctx.keyup(Some("x")).unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert!(section.handle_event(&mut ctx, &event));
+    assert_eq!(pressed.get(), 2);
+
+    // Messages for other sections are left alone.
+    let other = Event::ClientMessage {
+        args: vec!["other".into(), "x".into()],
+    };
+    assert!(!section.handle_event(&mut ctx, &other));
+    assert_eq!(pressed.get(), 2);
+
+    section.disable(&mut ctx).unwrap();
+    ctx.keypress("x").unwrap();
+    ctx.command(vec!["script-message".into(), "done".into()], false)
+        .unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert_eq!(
+        event,
+        Event::ClientMessage {
+            args: vec!["done".into()]
+        }
+    );
+}
+
+#[test]
+fn keybind() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.keybind("Ctrl+k", "script-message bound").unwrap();
+    ctx.keypress("Ctrl+k").unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert_eq!(
+        event,
+        Event::ClientMessage {
+            args: vec!["bound".into()]
+        }
+    );
+}
+
+#[test]
+fn invalid_keys() {
+    for key in ["", "a b", "x\ny", "#", "Ctrl+#"] {
+        let err = InputSection::new("bad-keys").bind(key, |_| {}).unwrap_err();
+        assert_eq!(err.code, MpvError::InvalidParameter, "{key:?}");
+        assert!(InputSection::new("bad-keys")
+            .bind_command(key, "ignore")
+            .is_err());
+    }
+    assert!(InputSection::new("bad-keys")
+        .bind_command("x", "ignore\ny ignore")
+        .is_err());
+    assert!(InputSection::new("bad-keys").bind("SHARP", |_| {}).is_ok());
+}
@@ -0,0 +1,158 @@
+#![cfg(all(unix, feature = "ipc", feature = "libmpv"))]
+
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+use common::*;
+use libmpv_rs::safe::{ipc::IpcServer, *};
+use serde_json::{json, Value};
+
+struct Peer {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Peer {
+    fn connect(server: &IpcServer) -> Self {
+        let writer = UnixStream::connect(server.path()).unwrap();
+        writer.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let reader = BufReader::new(writer.try_clone().unwrap());
+        Self { reader, writer }
+    }
+
+    fn send(&mut self, line: &str) {
+        self.writer.write_all(line.as_bytes()).unwrap();
+        self.writer.write_all(b"\n").unwrap();
+    }
+
+    fn recv(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// Skip events until a message matches `pred`.
+    fn recv_until(&mut self, pred: impl Fn(&Value) -> bool) -> Value {
+        loop {
+            let msg = self.recv();
+            if pred(&msg) {
+                return msg;
+            }
+        }
+    }
+
+    fn request(&mut self, request: Value) -> Value {
+        let id = request["request_id"].clone();
+        self.send(&request.to_string());
+        self.recv_until(|msg| msg.get("event").is_none() && msg["request_id"] == id)
+    }
+}
+
+fn socket_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("libmpv-rs-{}-{name}.sock", std::process::id()))
+}
+
+#[test]
+fn properties() {
+    let mut ctx = MpvHandle::for_tests();
+    let server = IpcServer::start(&mut ctx, socket_path("properties")).unwrap();
+    let mut peer = Peer::connect(&server);
+
+    let reply = peer.request(json!({"command": ["get_property", "idle-active"], "request_id": 1}));
+    assert_eq!(
+        reply,
+        json!({"data": true, "error": "success", "request_id": 1})
+    );
+
+    let reply = peer.request(json!({"command": ["set_property", "volume", 25], "request_id": 2}));
+    assert_eq!(reply["error"], "success");
+    let reply = peer.request(json!({"command": ["get_property", "volume"], "request_id": 3}));
+    assert_eq!(reply["data"], 25.0);
+
+    let reply =
+        peer.request(json!({"command": ["get_property_string", "volume"], "request_id": 4}));
+    assert_eq!(reply["data"], "25.000000");
+
+    let reply =
+        peer.request(json!({"command": ["get_property", "no-such-property"], "request_id": 5}));
+    assert_eq!(reply["error"], "property not found");
+}
+
+#[test]
+fn commands() {
+    let mut ctx = MpvHandle::for_tests();
+    let server = IpcServer::start(&mut ctx, socket_path("commands")).unwrap();
+    let mut peer = Peer::connect(&server);
+
+    let reply = peer.request(json!({"command": ["expand-text", "x${=pause}"], "request_id": 1}));
+    assert_eq!(reply["data"], "xno");
+
+    let reply =
+        peer.request(json!({"command": {"name": "expand-text", "text": "named"}, "request_id": 2}));
+    assert_eq!(reply["data"], "named");
+
+    let reply =
+        peer.request(json!({"command": ["expand-text", "async"], "request_id": 3, "async": true}));
+    assert_eq!(reply["data"], "async");
+
+    let reply = peer.request(json!({"command": ["client_name"], "request_id": 4}));
+    assert!(reply["data"].as_str().unwrap().starts_with("ipc"));
+
+    peer.send("not json {");
+    peer.send("{\"oops\"");
+    let reply = peer.recv_until(|msg| msg.get("event").is_none());
+    assert_eq!(reply["error"], "invalid parameter");
+
+    // Plain input.conf commands get no reply.
+    peer.send("set pause yes");
+    let reply = peer.request(json!({"command": ["get_property", "pause"], "request_id": 5}));
+    assert_eq!(reply["data"], true);
+}
+
+#[test]
+fn observe_and_events() {
+    let mut ctx = MpvHandle::for_tests();
+    let server = IpcServer::start(&mut ctx, socket_path("events")).unwrap();
+    let mut peer = Peer::connect(&server);
+
+    let reply =
+        peer.request(json!({"command": ["observe_property", 42, "pause"], "request_id": 1}));
+    assert_eq!(reply["error"], "success");
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+    peer.recv_until(|msg| {
+        *msg == json!({"event": "property-change", "id": 42, "name": "pause", "data": true})
+    });
+
+    ctx.command(vec!["loadfile".into(), VIDEO.into()], false)
+        .unwrap();
+    peer.recv_until(|msg| msg["event"] == "file-loaded");
+
+    ctx.command(vec!["stop".into()], false).unwrap();
+    let msg = peer.recv_until(|msg| msg["event"] == "end-file");
+    assert_eq!(msg["reason"], "stop");
+
+    ctx.command(vec!["quit".into()], false).unwrap();
+    peer.recv_until(|msg| msg["event"] == "shutdown");
+}
+
+#[test]
+fn drop_closes_connections() {
+    let mut ctx = MpvHandle::for_tests();
+    let server = IpcServer::start(&mut ctx, socket_path("drop")).unwrap();
+    let mut peer = Peer::connect(&server);
+    // Make sure the connection is being served before dropping the server.
+    let reply = peer.request(json!({"command": ["get_property", "idle-active"], "request_id": 1}));
+    assert_eq!(reply["error"], "success");
+
+    drop(server);
+    // Pending events may still arrive before the end of the stream.
+    let mut line = String::new();
+    while peer.reader.read_line(&mut line).unwrap() > 0 {
+        line.clear();
+    }
+}
@@ -0,0 +1,200 @@
+#![cfg(all(unix, feature = "ipc"))]
+
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    thread,
+};
+
+use common::*;
+#[cfg(feature = "libmpv")]
+use libmpv_rs::safe::ipc::IpcServer;
+use libmpv_rs::safe::{ipc::IpcClient, *};
+use serde_json::{json, Value};
+
+/// Stand in for mpv: answer each request with the next scripted reply, after
+/// sending the scripted events. Replies get the request's ID.
+fn stand_in(script: Vec<(Vec<Value>, Value)>) -> (IpcClient, thread::JoinHandle<Vec<Value>>) {
+    let (client, server) = UnixStream::pair().unwrap();
+    server.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let thread = thread::spawn(move || {
+        let mut reader = BufReader::new(server.try_clone().unwrap());
+        let mut writer = server;
+        let mut requests = vec![];
+        for (events, mut reply) in script {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let request: Value = serde_json::from_str(&line).unwrap();
+            reply["request_id"] = request["request_id"].clone();
+            for msg in events.iter().chain([&reply]) {
+                writeln!(writer, "{msg}").unwrap();
+            }
+            requests.push(request["command"].clone());
+        }
+        requests
+    });
+    (IpcClient::from_stream(client).unwrap(), thread)
+}
+
+#[test]
+fn stand_in_requests() {
+    let (mut client, thread) = stand_in(vec![
+        (vec![], json!({"data": 50.5, "error": "success"})),
+        (vec![], json!({"error": "property not found"})),
+        (vec![], json!({"data": null, "error": "success"})),
+        (vec![], json!({"data": ["a", 1], "error": "success"})),
+    ]);
+
+    assert_eq!(
+        client.get_property("volume".into()),
+        Ok(Node::Float64(50.5))
+    );
+    let err = client.get_property("nope".into()).unwrap_err();
+    assert_eq!(err.code, MpvError::PropertyNotFound);
+    assert_eq!(err.operation, Operation::GetProperty("nope".into()));
+    client
+        .set_property("pause".into(), Node::Flag(true))
+        .unwrap();
+    let res = client
+        .command(vec!["expand-text".into(), "x".into()], true)
+        .unwrap();
+    assert_eq!(res, Some(Node::Array(vec!["a".into(), Node::Int64(1)])));
+
+    let requests = thread.join().unwrap();
+    assert_eq!(
+        requests,
+        vec![
+            json!(["get_property", "volume"]),
+            json!(["get_property", "nope"]),
+            json!(["set_property", "pause", true]),
+            json!(["expand-text", "x"]),
+        ]
+    );
+
+    // The stand-in hung up.
+    assert_eq!(client.wait_event(1.0), Some(Event::Shutdown));
+    let err = client.get_property("volume".into()).unwrap_err();
+    assert_eq!(err.code, MpvError::Uninitialized);
+}
+
+#[test]
+fn stand_in_events() {
+    let events = vec![
+        json!({"event": "start-file", "playlist_entry_id": 1}),
+        json!({"event": "property-change", "id": 7, "name": "volume", "data": 100}),
+        json!({"event": "end-file", "reason": "error", "playlist_entry_id": 1,
+               "file_error": "unrecognized file format"}),
+        json!({"event": "client-message", "args": ["hello", "world"]}),
+    ];
+    let (mut client, thread) = stand_in(vec![
+        (vec![], json!({"error": "success"})),
+        (events, json!({"error": "success"})),
+    ]);
+
+    client
+        .observe_property("volume".into(), MpvFormat::Float64, 7)
+        .unwrap();
+    // Events that arrive before a reply are kept for wait_event().
+    client.command(vec!["stop".into()], false).unwrap();
+    thread.join().unwrap();
+
+    assert_eq!(
+        client.wait_event(0.0),
+        Some(Event::StartFile {
+            playlist_entry_id: 1
+        })
+    );
+    assert_eq!(
+        client.wait_event(0.0),
+        Some(Event::PropertyChange {
+            result: Ok(Some(Property {
+                name: "volume".into(),
+                data: Some(Node::Float64(100.0)),
+            })),
+            reply_userdata: 7,
+        })
+    );
+    assert_eq!(
+        client.wait_event(0.0),
+        Some(Event::EndFile {
+            reason: EndFileReason::Error(MpvError::UnknownFormat),
+            playlist_entry_id: 1,
+            playlist_insert_id: 0,
+            playlist_insert_num_entries: 0,
+        })
+    );
+    assert_eq!(
+        client.wait_event(0.0),
+        Some(Event::ClientMessage {
+            args: vec!["hello".into(), "world".into()]
+        })
+    );
+}
+
+#[test]
+fn stand_in_timeout() {
+    let (client, server) = UnixStream::pair().unwrap();
+    let mut client = IpcClient::from_stream(client).unwrap();
+    assert_eq!(client.wait_event(0.0), None);
+    assert_eq!(client.wait_event(0.05), None);
+    // A message split across reads is not lost.
+    let mut writer = server;
+    writer.write_all(br#"{"event": "id"#).unwrap();
+    assert_eq!(client.wait_event(0.05), None);
+    writer.write_all(b"le\"}\n").unwrap();
+    assert_eq!(client.wait_event(1.0), Some(Event::Idle));
+}
+
+/// Works the same with any player.
+#[cfg(feature = "libmpv")]
+fn volume_round_trip(player: &mut impl Player) {
+    player
+        .set_property("volume".into(), Node::Float64(30.0))
+        .unwrap();
+    player
+        .observe_property("volume".into(), MpvFormat::Float64, 3)
+        .unwrap();
+    let event = loop {
+        match player.wait_event(TIMEOUT.as_secs_f64()) {
+            Some(event @ Event::PropertyChange { .. }) => break event,
+            Some(_) => {}
+            None => panic!("timed out waiting for event"),
+        }
+    };
+    assert_eq!(
+        event,
+        Event::PropertyChange {
+            result: Ok(Some(Property {
+                name: "volume".into(),
+                data: Some(Node::Float64(30.0)),
+            })),
+            reply_userdata: 3,
+        }
+    );
+    player.unobserve_property(3).unwrap();
+    assert_eq!(
+        player.get_property("volume".into()),
+        Ok(Node::Float64(30.0))
+    );
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn remote_player() {
+    let mut ctx = MpvHandle::for_tests();
+    volume_round_trip(&mut ctx);
+
+    let path =
+        std::env::temp_dir().join(format!("libmpv-rs-{}-ipc-client.sock", std::process::id()));
+    let server = IpcServer::start(&mut ctx, &path).unwrap();
+    let mut client = IpcClient::connect(server.path()).unwrap();
+    volume_round_trip(&mut client);
+
+    let err = client
+        .command(vec!["no-such-command".into()], false)
+        .unwrap_err();
+    assert_eq!(err.code, MpvError::InvalidParameter);
+    assert_eq!(err.operation, Operation::Command("no-such-command".into()));
+}
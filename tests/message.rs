@@ -0,0 +1,87 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::*;
+use libmpv_rs::safe::*;
+
+fn client_message(event: &Event) -> bool {
+    matches!(event, Event::ClientMessage { .. })
+}
+
+#[test]
+fn dispatch_by_name() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut plugin = ctx.create_client(c"plugin").unwrap();
+    let received = Rc::new(RefCell::new(vec![]));
+    let mut dispatcher = MessageDispatcher::new();
+    {
+        let received = received.clone();
+        dispatcher.register("ping", move |ctx, args| {
+            received.borrow_mut().push(args.to_vec());
+            ctx.script_message_to("plugin", &["pong"]).unwrap();
+        });
+    }
+
+    let target = ctx.name();
+    plugin
+        .script_message_to(&target, &["ping", "a", "b"])
+        .unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert!(dispatcher.handle_event(&mut ctx, &event));
+    assert_eq!(*received.borrow(), vec![vec!["a".to_owned(), "b".into()]]);
+
+    // The reply only goes to the plugin.
+    let event = wait_for(&mut plugin, client_message);
+    assert_eq!(
+        event,
+        Event::ClientMessage {
+            args: vec!["pong".into()]
+        }
+    );
+
+    // Broadcasts reach every client.
+    plugin.script_message(&["unknown"]).unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert!(!dispatcher.handle_event(&mut ctx, &event));
+
+    assert!(dispatcher.unregister("ping"));
+    plugin.script_message(&["ping"]).unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert!(!dispatcher.handle_event(&mut ctx, &event));
+    assert_eq!(received.borrow().len(), 1);
+}
+
+#[test]
+fn unknown_target() {
+    let mut ctx = MpvHandle::for_tests();
+    let err = ctx.script_message_to("nobody", &["x"]).unwrap_err();
+    assert_eq!(
+        err.operation,
+        Operation::Command("script-message-to".into())
+    );
+}
+
+#[test]
+fn input_sections() {
+    let mut ctx = MpvHandle::for_tests();
+    let pressed = Rc::new(RefCell::new(vec![]));
+    let section = {
+        let pressed = pressed.clone();
+        InputSection::new("dispatch-keys")
+            .mode(SectionMode::Force)
+            .bind("z", move |_| pressed.borrow_mut().push("z"))
+            .unwrap()
+    };
+    section.define(&mut ctx).unwrap();
+    section.enable(&mut ctx, EnableFlags::default()).unwrap();
+    let mut dispatcher = MessageDispatcher::new();
+    dispatcher.register_section(section);
+
+    ctx.keypress("z").unwrap();
+    let event = wait_for(&mut ctx, client_message);
+    assert!(dispatcher.handle_event(&mut ctx, &event));
+    assert_eq!(*pressed.borrow(), vec!["z"]);
+}
@@ -0,0 +1,218 @@
+//! `MockPlayer` runs without mpv; these tests never create a player.
+
+use libmpv_rs::safe::*;
+
+/// Example of playback logic written against `Player`: play `url`, and skip
+/// to the next file when the current one fails to load.
+fn play_with_fallback(player: &mut impl Player, urls: &[&str]) -> Result<Option<usize>, Error> {
+    for (i, url) in urls.iter().enumerate() {
+        player.command(vec!["loadfile".into(), (*url).into()], false)?;
+        loop {
+            match player.wait_event(-1.0) {
+                Some(Event::FileLoaded) => return Ok(Some(i)),
+                Some(Event::EndFile { .. }) => break,
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn strings(args: &[&str]) -> Node {
+    Node::Array(args.iter().map(|&arg| arg.into()).collect())
+}
+
+#[test]
+fn records_commands() {
+    let mut player = MockPlayer::new();
+    player.push_event(Event::EndFile {
+        reason: EndFileReason::Error(MpvError::LoadingFailed),
+        playlist_entry_id: 1,
+        playlist_insert_id: 0,
+        playlist_insert_num_entries: 0,
+    });
+    player.push_event(Event::FileLoaded);
+
+    assert_eq!(
+        play_with_fallback(&mut player, &["a", "b", "c"]),
+        Ok(Some(1))
+    );
+    assert_eq!(
+        player.take_commands(),
+        vec![strings(&["loadfile", "a"]), strings(&["loadfile", "b"])]
+    );
+    assert!(player.commands().is_empty());
+
+    let map = Node::Map([("name".to_owned(), Node::from("stop"))].into());
+    player.command_node(map.clone(), false).unwrap();
+    assert_eq!(player.commands(), &[map]);
+}
+
+#[test]
+fn scripted_results() {
+    let mut player = MockPlayer::new();
+    player.push_result("expand-text", Ok(Some("x".into())));
+    player.push_result("loadfile", Err(MpvError::InvalidParameter));
+
+    let res = player.command(vec!["expand-text".into()], true);
+    assert_eq!(res, Ok(Some(Node::String("x".into()))));
+    // Used up.
+    let res = player.command(vec!["expand-text".into()], true);
+    assert_eq!(res, Ok(None));
+
+    let err = play_with_fallback(&mut player, &["a"]).unwrap_err();
+    assert_eq!(err.code, MpvError::InvalidParameter);
+    assert_eq!(err.operation, Operation::Command("loadfile".into()));
+}
+
+#[test]
+fn properties() {
+    let mut player = MockPlayer::new();
+    player.set("volume", 100.0);
+    assert_eq!(
+        player.get_property("volume".into()),
+        Ok(Node::Float64(100.0))
+    );
+    let err = player.get_property("pause".into()).unwrap_err();
+    assert_eq!(err.code, MpvError::PropertyUnavailable);
+
+    player.set_property("pause".into(), true.into()).unwrap();
+    assert_eq!(player.get("pause"), Some(&Node::Flag(true)));
+}
+
+#[test]
+fn observed_properties() {
+    let change = |data: Option<Node>| Event::PropertyChange {
+        result: Ok(Some(Property {
+            name: "pause".into(),
+            data,
+        })),
+        reply_userdata: 5,
+    };
+    let mut player = MockPlayer::new();
+    player.set("pause", false);
+    player
+        .observe_property("pause".into(), MpvFormat::Flag, 5)
+        .unwrap();
+    assert!(player.is_observed("pause"));
+    assert_eq!(player.wait_event(0.0), Some(change(Some(false.into()))));
+    assert_eq!(player.wait_event(0.0), None);
+
+    // No event if the value stays the same.
+    player.set("pause", false);
+    player.set("pause", true);
+    player.unset("pause");
+    assert_eq!(player.wait_event(0.0), Some(change(Some(true.into()))));
+    assert_eq!(player.wait_event(0.0), Some(change(None)));
+
+    player.unobserve_property(5).unwrap();
+    assert!(!player.is_observed("pause"));
+    player.set("pause", true);
+    assert_eq!(player.wait_event(0.0), None);
+}
+
+#[test]
+fn load_and_wait() {
+    let mut player = MockPlayer::new();
+    player.push_event(Event::EndFile {
+        reason: EndFileReason::Error(MpvError::LoadingFailed),
+        playlist_entry_id: 1,
+        playlist_insert_id: 0,
+        playlist_insert_num_entries: 0,
+    });
+    player.push_event(Event::StartFile {
+        playlist_entry_id: 2,
+    });
+    player.push_event(Event::FileLoaded);
+    assert_eq!(player.load_and_wait("b"), Ok(()));
+    assert_eq!(player.commands(), [strings(&["loadfile", "b"])]);
+
+    player.push_event(Event::StartFile {
+        playlist_entry_id: 3,
+    });
+    player.push_event(Event::EndFile {
+        reason: EndFileReason::Error(MpvError::UnknownFormat),
+        playlist_entry_id: 3,
+        playlist_insert_id: 0,
+        playlist_insert_num_entries: 0,
+    });
+    assert_eq!(
+        player.load_and_wait("c"),
+        Err(Error::new(
+            Operation::Command("loadfile".into()),
+            MpvError::UnknownFormat
+        ))
+    );
+
+    assert_eq!(
+        player.wait_for(|_| true, 0.0),
+        Err(WaitError::Timeout(Operation::WaitEvent))
+    );
+    // Timeouts too long for a `Duration` wait forever.
+    for timeout in [f64::INFINITY, f64::NAN, 1e300] {
+        player.push_event(Event::Idle);
+        assert_eq!(player.wait_for(|_| true, timeout), Ok(Event::Idle));
+    }
+}
+
+#[test]
+fn load_and_wait_skips_previous_file() {
+    let end_file = |reason, playlist_entry_id| Event::EndFile {
+        reason,
+        playlist_entry_id,
+        playlist_insert_id: 0,
+        playlist_insert_num_entries: 0,
+    };
+    let entry_id = |id: i64| {
+        Ok(Some(Node::Map(
+            [("playlist_entry_id".into(), id.into())].into(),
+        )))
+    };
+    let mut player = MockPlayer::new();
+    player.push_result("loadfile", entry_id(5));
+    // Still loading the file played before.
+    player.push_event(Event::StartFile {
+        playlist_entry_id: 4,
+    });
+    player.push_event(Event::FileLoaded);
+    player.push_event(end_file(EndFileReason::Stop, 4));
+    player.push_event(Event::StartFile {
+        playlist_entry_id: 5,
+    });
+    player.push_event(Event::FileLoaded);
+    player.push_event(Event::PlaybackRestart);
+    assert_eq!(player.load_and_wait("b"), Ok(()));
+    assert_eq!(player.wait_event(0.0), Some(Event::PlaybackRestart));
+
+    // A playlist is followed to its first entry.
+    player.push_result("loadfile", entry_id(6));
+    player.push_event(Event::StartFile {
+        playlist_entry_id: 6,
+    });
+    player.push_event(Event::EndFile {
+        reason: EndFileReason::Redirect,
+        playlist_entry_id: 6,
+        playlist_insert_id: 7,
+        playlist_insert_num_entries: 1,
+    });
+    player.push_event(Event::StartFile {
+        playlist_entry_id: 7,
+    });
+    player.push_event(end_file(EndFileReason::Error(MpvError::UnknownFormat), 7));
+    assert_eq!(
+        player.load_and_wait("list.m3u"),
+        Err(Error::new(
+            Operation::Command("loadfile".into()),
+            MpvError::UnknownFormat
+        ))
+    );
+}
+
+#[test]
+#[should_panic(expected = "no scripted events")]
+fn wait_forever_without_events() {
+    let mut player = MockPlayer::new();
+    player.push_event(Event::Idle);
+    let _ = player.wait_for(|event| *event == Event::FileLoaded, -1.0);
+}
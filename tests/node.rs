@@ -0,0 +1,74 @@
+//! `Node` conversions in both directions, using the "user-data" property as a
+//! store that accepts any node type.
+
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::collections::HashMap;
+
+use libmpv_rs::safe::*;
+
+fn round_trip(node: Node) -> Node {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.set_property("user-data/test".into(), node).unwrap();
+    ctx.get_property("user-data/test".into()).unwrap()
+}
+
+#[test]
+fn scalars() {
+    assert_eq!(round_trip(Node::from("text")), Node::String("text".into()));
+    assert_eq!(round_trip(Node::from("")), Node::String("".into()));
+    assert_eq!(
+        round_trip(Node::from("ünïcødé")),
+        Node::String("ünïcødé".into())
+    );
+    assert_eq!(round_trip(Node::from(true)), Node::Flag(true));
+    assert_eq!(round_trip(Node::from(false)), Node::Flag(false));
+    assert_eq!(round_trip(Node::from(i64::MIN)), Node::Int64(i64::MIN));
+    assert_eq!(round_trip(Node::from(-0.25)), Node::Float64(-0.25));
+}
+
+#[test]
+fn arrays() {
+    assert_eq!(round_trip(Node::Array(vec![])), Node::Array(vec![]));
+    let array = Node::Array(vec![
+        Node::from(1i64),
+        Node::from("two"),
+        Node::Array(vec![Node::from(3.0)]),
+    ]);
+    assert_eq!(round_trip(array.clone()), array);
+}
+
+#[test]
+fn maps() {
+    assert_eq!(
+        round_trip(Node::Map(HashMap::new())),
+        Node::Map(HashMap::new())
+    );
+    let map = Node::Map(HashMap::from([
+        ("a".to_string(), Node::from(1i64)),
+        ("b".to_string(), Node::Array(vec![Node::from(true)])),
+        (
+            "c".to_string(),
+            Node::Map(HashMap::from([("d".to_string(), Node::from("e"))])),
+        ),
+    ]));
+    assert_eq!(round_trip(map.clone()), map);
+}
+
+#[test]
+fn many_conversions() {
+    // Repeated allocation and release, to shake out double frees and leaks
+    // under a sanitizer or valgrind.
+    let mut ctx = MpvHandle::for_tests();
+    for i in 0..1000 {
+        let node = Node::Map(HashMap::from([(
+            format!("key{i}"),
+            Node::Array(vec![Node::from(format!("value{i}")), Node::from(i)]),
+        )]));
+        ctx.set_property("user-data/test".into(), node.clone())
+            .unwrap();
+        assert_eq!(ctx.get_property("user-data/test".into()).unwrap(), node);
+    }
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn overlay() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+
+    let mut overlay = OsdOverlay::new(&mut ctx).unwrap();
+    overlay.res_x = 1280;
+    overlay.res_y = 720;
+    overlay.data = r"{\an7\pos(100,50)\fs40}Hello".into();
+    overlay.update().unwrap();
+    if let Some(bounds) = overlay.update_with_bounds().unwrap() {
+        assert!(bounds.x0 >= 100 && bounds.x1 > bounds.x0);
+        assert!(bounds.y0 >= 50 && bounds.y1 > bounds.y0);
+    }
+
+    overlay.data.clear();
+    assert_eq!(overlay.update_with_bounds(), Ok(None));
+
+    overlay.hidden = true;
+    overlay.z = 5;
+    overlay.update().unwrap();
+    overlay.remove().unwrap();
+    drop(overlay);
+
+    // Every overlay has its own namespace of IDs.
+    let mut a = OsdOverlay::new(&mut ctx).unwrap();
+    let mut b = OsdOverlay::new(&mut ctx).unwrap();
+    a.data = "a".into();
+    b.data = "b".into();
+    a.update().unwrap();
+    b.update().unwrap();
+}
+
+#[test]
+fn bounds_from_node() {
+    let node = Node::Map(HashMap::from([
+        ("x0".to_owned(), Node::Int64(1)),
+        ("y0".to_owned(), Node::Int64(2)),
+        ("x1".to_owned(), Node::Int64(3)),
+        ("y1".to_owned(), Node::Int64(4)),
+    ]));
+    assert_eq!(
+        Bounds::from_node(&node),
+        Some(Bounds {
+            x0: 1,
+            y0: 2,
+            x1: 3,
+            y1: 4
+        })
+    );
+    assert_eq!(Bounds::from_node(&Node::Map(HashMap::new())), None);
+}
+
+#[test]
+fn show_text() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.show_text("volume: ${volume}", Some(1000), None)
+        .unwrap();
+    ctx.show_text("level 0", None, Some(0)).unwrap();
+    play(&mut ctx, VIDEO);
+    ctx.show_progress().unwrap();
+}
+
+#[test]
+fn outlived_by_overlay() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut overlay = OsdOverlay::new(&mut ctx).unwrap();
+    overlay.update().unwrap();
+    ctx.terminate();
+    assert_eq!(
+        overlay.update().map_err(|err| err.code),
+        Err(MpvError::Uninitialized)
+    );
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+/// A half transparent red square, premultiplied.
+fn square(size: u32) -> Frame {
+    Frame {
+        width: size,
+        height: size,
+        stride: size as i64 * 4,
+        format: "bgra".into(),
+        data: [0, 0, 128, 128].repeat((size * size) as usize),
+    }
+}
+
+#[test]
+fn memory() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+
+    let mut overlay = BitmapOverlay::new(&mut ctx, 1, square(8), 4, 4).unwrap();
+    assert_eq!(overlay.id(), 1);
+    overlay.set_position(10, 20).unwrap();
+    overlay.set_display_size(Some((16, 16))).unwrap();
+    overlay.set_display_size(None).unwrap();
+    drop(overlay);
+
+    // Padded rows are fine.
+    let mut padded = square(4);
+    padded.stride = 32;
+    padded.data = vec![255; 32 * 4];
+    BitmapOverlay::new(&mut ctx, 2, padded, 0, 0).unwrap();
+}
+
+#[test]
+fn invalid() {
+    let mut ctx = MpvHandle::for_tests();
+    let code = |res: Result<BitmapOverlay, Error>| res.err().map(|err| err.code);
+
+    let mut frame = square(4);
+    frame.format = "bgr0".into();
+    assert_eq!(
+        code(BitmapOverlay::new(&mut ctx, 0, frame, 0, 0)),
+        Some(MpvError::InvalidParameter)
+    );
+    let mut frame = square(4);
+    frame.data.truncate(10);
+    assert_eq!(
+        code(BitmapOverlay::new(&mut ctx, 0, frame, 0, 0)),
+        Some(MpvError::InvalidParameter)
+    );
+    // mpv only has 64 overlay slots.
+    assert!(BitmapOverlay::new(&mut ctx, 64, square(4), 0, 0).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn fd() {
+    use std::{io::Write, os::fd::OwnedFd};
+
+    let mut ctx = MpvHandle::for_tests();
+    let path = std::env::temp_dir().join(format!("libmpv-rs-{}-overlay.bgra", std::process::id()));
+    let mut file = std::fs::File::create(&path).unwrap();
+    // A header to skip, then the pixels.
+    file.write_all(&[0; 16]).unwrap();
+    file.write_all(&square(4).data).unwrap();
+    drop(file);
+
+    let fd = OwnedFd::from(std::fs::File::open(&path).unwrap());
+    let overlay = BitmapOverlay::from_fd(&mut ctx, 3, fd, 16, 4, 4, 16, 0, 0);
+    std::fs::remove_file(&path).unwrap();
+    overlay.unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_with_short_stride() {
+    use std::os::fd::OwnedFd;
+
+    let mut ctx = MpvHandle::for_tests();
+    let fd = OwnedFd::from(std::fs::File::open("/dev/null").unwrap());
+    let err = BitmapOverlay::from_fd(&mut ctx, 3, fd, 0, 4, 4, 8, 0, 0)
+        .err()
+        .unwrap();
+    assert_eq!(err.code, MpvError::InvalidParameter);
+}
+
+#[test]
+fn outlived_by_overlay() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut other = ctx.create_client(c"other").unwrap();
+    play(&mut ctx, VIDEO);
+    let mut overlay = BitmapOverlay::new(&mut ctx, 3, square(8), 0, 0).unwrap();
+    // The player lives on, without the overlay.
+    drop(ctx);
+    assert_eq!(
+        overlay.set_position(1, 1).map_err(|err| err.code),
+        Err(MpvError::Uninitialized)
+    );
+    drop(overlay);
+    assert!(other.get_property("time-pos".into()).is_ok());
+    other.terminate();
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "record")]
+
+mod common;
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "libmpv")]
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn record_and_replay() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.observe_property("pause".into(), MpvFormat::Flag, 1)
+        .unwrap();
+    loadfile(&mut ctx, SHORT_VIDEO);
+
+    let mut recorder = EventRecorder::new(Vec::new());
+    let mut live = vec![];
+    let deadline = Instant::now() + TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(event) = recorder.wait_event(&mut ctx, 0.1) {
+            let done = matches!(event, Event::EndFile { .. });
+            live.push(event);
+            if done {
+                break;
+            }
+        }
+    }
+    let trace = recorder.finish().unwrap();
+    assert!(live.contains(&Event::FileLoaded));
+
+    let mut replayer = EventReplayer::from_reader(trace.as_slice()).unwrap();
+    assert_eq!(replayer.remaining(), live.len());
+    let mut replayed = vec![];
+    let mut last_time = i64::MIN;
+    while let Some(event) = replayer.wait_event(0.0) {
+        let time = replayer.time_ns().unwrap();
+        assert!(time >= last_time);
+        last_time = time;
+        replayed.push(event);
+    }
+    assert_eq!(replayed, live);
+}
+
+#[test]
+fn all_node_types() {
+    let event = Event::PropertyChange {
+        result: Ok(Some(Property {
+            name: "user-data/x".into(),
+            data: Some(Node::Map(HashMap::from([
+                ("bytes".to_owned(), Node::ByteArray(vec![0, 255])),
+                ("int".to_owned(), Node::Int64(1)),
+                ("float".to_owned(), Node::Float64(1.0)),
+                ("osd".to_owned(), Node::OsdString("1".into())),
+            ]))),
+        })),
+        reply_userdata: 3,
+    };
+    let errors = Event::EndFile {
+        reason: EndFileReason::Error(MpvError::UnknownFormat),
+        playlist_entry_id: 1,
+        playlist_insert_id: 0,
+        playlist_insert_num_entries: 0,
+    };
+
+    let mut recorder = EventRecorder::new(Vec::new());
+    recorder.record(5, &event).unwrap();
+    recorder.record(7, &errors).unwrap();
+    let trace = recorder.finish().unwrap();
+
+    let mut replayer = EventReplayer::from_reader(trace.as_slice()).unwrap();
+    assert_eq!(replayer.wait_event(0.0), Some(event));
+    assert_eq!(replayer.time_ns(), Some(5));
+    assert_eq!(replayer.wait_event(0.0), Some(errors));
+    assert_eq!(replayer.wait_event(0.0), None);
+}
+
+#[test]
+fn non_finite_floats() {
+    let change = |value| Event::PropertyChange {
+        result: Ok(Some(Property {
+            name: "speed".into(),
+            data: Some(Node::Float64(value)),
+        })),
+        reply_userdata: 1,
+    };
+    let values = [f64::INFINITY, f64::NEG_INFINITY, f64::NAN, -0.5];
+    let mut recorder = EventRecorder::new(Vec::new());
+    for (time, value) in values.into_iter().enumerate() {
+        recorder.record(time as i64, &change(value)).unwrap();
+    }
+    let trace = recorder.finish().unwrap();
+
+    let mut replayer = EventReplayer::from_reader(trace.as_slice()).unwrap();
+    assert_eq!(replayer.wait_event(0.0), Some(change(f64::INFINITY)));
+    assert_eq!(replayer.wait_event(0.0), Some(change(f64::NEG_INFINITY)));
+    let Some(Event::PropertyChange {
+        result:
+            Ok(Some(Property {
+                data: Some(Node::Float64(nan)),
+                ..
+            })),
+        ..
+    }) = replayer.wait_event(0.0)
+    else {
+        panic!("not a float change");
+    };
+    assert!(nan.is_nan());
+    assert_eq!(replayer.wait_event(0.0), Some(change(-0.5)));
+}
+
+#[test]
+fn realtime() {
+    let events = vec![
+        RecordedEvent {
+            time_ns: 1_000_000_000,
+            event: Event::Idle,
+        },
+        RecordedEvent {
+            time_ns: 1_200_000_000,
+            event: Event::Shutdown,
+        },
+    ];
+    let mut replayer = EventReplayer::new(events).realtime(true);
+    let start = Instant::now();
+    assert_eq!(replayer.wait_event(-1.0), Some(Event::Idle));
+    // Not due yet.
+    assert_eq!(replayer.wait_event(0.0), None);
+    assert_eq!(replayer.wait_event(-1.0), Some(Event::Shutdown));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn invalid_trace() {
+    let err = EventReplayer::from_reader(&b"{\"time_ns\": 1}\n"[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
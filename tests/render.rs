@@ -0,0 +1,81 @@
+//! The software renderer of the render API, driven through the raw bindings.
+
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::{ffi::c_void, ptr::null_mut, time::Instant};
+
+use common::*;
+use libmpv_rs::{raw::*, safe::*};
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+
+#[test]
+fn sw_render() {
+    let mut ctx = MpvBuilder::headless().vo("libmpv").build().unwrap();
+
+    let mut render: *mut mpv_render_context = null_mut();
+    let mut params = [
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_API_TYPE,
+            data: MPV_RENDER_API_TYPE_SW.as_ptr() as *mut c_void,
+        },
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_INVALID,
+            data: null_mut(),
+        },
+    ];
+    let status =
+        unsafe { mpv_render_context_create(&mut render, ctx.as_raw(), params.as_mut_ptr()) };
+    assert_eq!(status, 0, "mpv_render_context_create failed");
+
+    play(&mut ctx, VIDEO);
+
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        assert!(Instant::now() < deadline, "no frame was rendered");
+        ctx.wait_event(0.01);
+        let flags = unsafe { mpv_render_context_update(render) };
+        if flags & mpv_render_update_flag_MPV_RENDER_UPDATE_FRAME as u64 != 0 {
+            break;
+        }
+    }
+
+    let mut size = [WIDTH as i32, HEIGHT as i32];
+    let mut stride = WIDTH * 4;
+    let mut pixels = vec![0u8; stride * HEIGHT];
+    let mut params = [
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_SW_SIZE,
+            data: size.as_mut_ptr() as *mut c_void,
+        },
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_SW_FORMAT,
+            data: c"rgb0".as_ptr() as *mut c_void,
+        },
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_SW_STRIDE,
+            data: &mut stride as *mut usize as *mut c_void,
+        },
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_SW_POINTER,
+            data: pixels.as_mut_ptr() as *mut c_void,
+        },
+        mpv_render_param {
+            type_: mpv_render_param_type_MPV_RENDER_PARAM_INVALID,
+            data: null_mut(),
+        },
+    ];
+    let status = unsafe { mpv_render_context_render(render, params.as_mut_ptr()) };
+    assert_eq!(status, 0, "mpv_render_context_render failed");
+
+    // The test pattern is colorful, so the frame can't be a single color.
+    let first = &pixels[..3];
+    assert!(pixels.chunks(4).any(|pixel| &pixel[..3] != first));
+
+    // The render context must be freed before the handle is destroyed.
+    unsafe { mpv_render_context_free(render) };
+    drop(ctx);
+}
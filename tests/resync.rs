@@ -0,0 +1,150 @@
+mod common;
+
+#[cfg(feature = "libmpv")]
+use std::{sync::mpsc, thread, time::Duration};
+
+#[cfg(feature = "libmpv")]
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn resyncs_after_overflow() {
+    let mut mock = MockPlayer::new();
+    mock.set("volume", Node::Float64(100.0));
+    mock.set("pause", Node::Flag(false));
+    let mut player = ResyncPlayer::new(mock);
+    let mut state = PlayerState::new(1);
+    state.observe_as::<f64>(&mut player, "volume").unwrap();
+    state.observe_as::<bool>(&mut player, "pause").unwrap();
+    while let Some(event) = player.wait_event(0.0) {
+        state.handle_event(&event);
+    }
+    assert_eq!(state.get::<f64>("volume"), Some(100.0));
+
+    // Lose the change events.
+    player.inner().set("volume", Node::Float64(20.0));
+    player.inner().set("pause", Node::Flag(true));
+    while player.inner().wait_event(0.0).is_some() {}
+    player.inner().push_event(Event::QueueOverflow);
+
+    assert_eq!(player.wait_event(0.0), Some(Event::QueueOverflow));
+    while let Some(event) = player.wait_event(0.0) {
+        state.handle_event(&event);
+    }
+    assert_eq!(state.get::<f64>("volume"), Some(20.0));
+    assert_eq!(state.get::<bool>("pause"), Some(true));
+    assert_eq!(player.overflows(), 1);
+
+    // Unobserved properties are no longer read.
+    state.clear(&mut player).unwrap();
+    player.inner().push_event(Event::QueueOverflow);
+    assert_eq!(player.wait_event(0.0), Some(Event::QueueOverflow));
+    assert_eq!(player.wait_event(0.0), None);
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn event_loop_resyncs_after_overflow() {
+    let mut event_loop = EventLoop::new(MpvHandle::for_tests());
+    let (tx, rx) = mpsc::channel();
+    event_loop
+        .on_property::<f64>("volume", move |_, volume| tx.send(volume).unwrap())
+        .unwrap();
+    event_loop.run_once(TIMEOUT.as_secs_f64());
+    assert_eq!(rx.try_recv(), Ok(Some(100.0)));
+
+    // Flood the queue with more client messages than it holds.
+    let ctx = event_loop.handle();
+    for i in 0..5000 {
+        ctx.command(vec!["script-message".into(), i.to_string()], false)
+            .unwrap();
+    }
+    ctx.set_property("volume".into(), Node::Float64(42.0))
+        .unwrap();
+    while event_loop.overflows() == 0 {
+        assert!(event_loop.run_once(TIMEOUT.as_secs_f64()));
+    }
+    assert_eq!(rx.try_iter().last(), Some(Some(42.0)));
+}
+
+#[cfg(feature = "libmpv")]
+fn flood(ctx: &mut MpvHandle, count: usize) {
+    for i in 0..count {
+        ctx.command(vec!["script-message".into(), i.to_string()], false)
+            .unwrap();
+    }
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn handle_resyncs_after_overflow() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.observe_property("volume".into(), MpvFormat::Float64, 7)
+        .unwrap();
+    flood(&mut ctx, 5000);
+    ctx.set_property("volume".into(), Node::Float64(42.0))
+        .unwrap();
+    wait_for(&mut ctx, |e| *e == Event::QueueOverflow);
+    assert_eq!(ctx.overflows(), 1);
+    assert_eq!(
+        ctx.wait_event(0.0),
+        Some(Event::PropertyChange {
+            result: Ok(Some(Property {
+                name: "volume".into(),
+                data: Some(Node::Float64(42.0)),
+            })),
+            reply_userdata: 7,
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn buffer_events() {
+    let mut ctx = MpvBuilder::headless().buffer_events(10000).build().unwrap();
+    flood(&mut ctx, 5000);
+    let mut messages = 0;
+    while messages < 5000 {
+        match ctx.wait_event(TIMEOUT.as_secs_f64()) {
+            Some(Event::ClientMessage { .. }) => messages += 1,
+            Some(Event::QueueOverflow) | None => panic!("events were lost"),
+            Some(_) => {}
+        }
+    }
+    assert_eq!(ctx.overflows(), 0);
+    ctx.terminate();
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn buffer_events_skips_stale_changes() {
+    let mut ctx = MpvBuilder::headless().buffer_events(2000).build().unwrap();
+    ctx.observe_property("volume".into(), MpvFormat::Float64, 7)
+        .unwrap();
+    // More than the buffer and mpv's queue together hold.
+    flood(&mut ctx, 5000);
+    ctx.set_property("volume".into(), Node::Float64(10.0))
+        .unwrap();
+    // Make room, so that the thread buffers the overflow and the change to 10
+    // behind it.
+    for _ in 0..1500 {
+        ctx.wait_event(TIMEOUT.as_secs_f64()).unwrap();
+    }
+    thread::sleep(Duration::from_millis(200));
+    ctx.set_property("volume".into(), Node::Float64(42.0))
+        .unwrap();
+
+    let mut overflowed = false;
+    while let Some(event) = ctx.wait_event(0.5) {
+        match event {
+            Event::QueueOverflow => overflowed = true,
+            Event::PropertyChange {
+                result: Ok(Some(Property { data, .. })),
+                reply_userdata: 7,
+            } if overflowed => assert_eq!(data, Some(Node::Float64(42.0))),
+            _ => {}
+        }
+    }
+    assert!(overflowed);
+    ctx.terminate();
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+#[test]
+fn screenshot_raw() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+    wait_for(&mut ctx, |e| matches!(e, Event::PlaybackRestart));
+
+    let frame = ctx.screenshot_raw(ScreenshotMode::Video).unwrap();
+    assert_eq!((frame.width, frame.height), (64, 48));
+    assert!(frame.stride >= 64 * 4);
+    assert_eq!(frame.data.len(), frame.stride as usize * 48);
+    assert_eq!(frame.row(47).map(<[u8]>::len), Some(64 * 4));
+    assert_eq!(frame.row(48), None);
+
+    #[cfg(feature = "image")]
+    {
+        let image = frame.to_rgba_image().unwrap();
+        assert_eq!(image.dimensions(), (64, 48));
+    }
+}
+
+#[test]
+fn screenshot_without_video_fails() {
+    let mut ctx = MpvHandle::for_tests();
+    let err = ctx.screenshot_raw(ScreenshotMode::Video).unwrap_err();
+    assert_eq!(err.operation, Operation::Command("screenshot-raw".into()));
+}
+
+#[test]
+fn frame_from_malformed_node() {
+    assert_eq!(Frame::from_node(Node::Int64(1)), None);
+    let node = Node::Map(
+        [
+            ("w".to_string(), Node::Int64(2)),
+            ("h".to_string(), Node::Int64(2)),
+            ("stride".to_string(), Node::Int64(8)),
+            ("format".to_string(), Node::from("bgr0")),
+            // One row short.
+            ("data".to_string(), Node::ByteArray(vec![0; 8])),
+        ]
+        .into(),
+    );
+    assert_eq!(Frame::from_node(node), None);
+}
+
+#[test]
+fn frame_with_short_stride() {
+    let node = Node::Map(
+        [
+            ("w".to_string(), Node::Int64(2)),
+            ("h".to_string(), Node::Int64(2)),
+            // Rows of 2 pixels take 8 bytes.
+            ("stride".to_string(), Node::Int64(4)),
+            ("format".to_string(), Node::from("bgr0")),
+            ("data".to_string(), Node::ByteArray(vec![0; 16])),
+        ]
+        .into(),
+    );
+    assert_eq!(Frame::from_node(node), None);
+}
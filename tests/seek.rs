@@ -0,0 +1,117 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+fn time_pos(ctx: &mut MpvHandle) -> f64 {
+    match ctx.get_property("time-pos".into()) {
+        Ok(Node::Float64(time)) => time,
+        other => panic!("no time-pos: {other:?}"),
+    }
+}
+
+#[test]
+fn seeks() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+    play(&mut ctx, VIDEO);
+    let mut nav = Navigator::new(&mut ctx).unwrap();
+
+    nav.seek_exact(1.0).unwrap();
+    assert!((time_pos(&mut ctx) - 1.0).abs() < 0.01);
+
+    nav.seek_keyframe(1.5).unwrap();
+    assert!(time_pos(&mut ctx) <= 1.5);
+
+    nav.seek(-0.5, SeekMode::Relative, SeekPrecision::Exact)
+        .unwrap();
+    nav.frame_step().unwrap();
+    let stepped = time_pos(&mut ctx);
+    nav.frame_back_step().unwrap();
+    assert!((stepped - time_pos(&mut ctx) - 0.1).abs() < 0.01);
+
+    nav.seek(50.0, SeekMode::AbsolutePercent, SeekPrecision::Exact)
+        .unwrap();
+    assert!((time_pos(&mut ctx) - 1.0).abs() < 0.01);
+    nav.revert_seek().unwrap();
+
+    // Events of the original handle are left alone.
+    wait_for(&mut ctx, |e| matches!(e, Event::PlaybackRestart));
+}
+
+#[test]
+fn nothing_loaded() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut nav = Navigator::new(&mut ctx).unwrap();
+    nav.timeout = 0.5;
+    assert!(nav.seek_exact(1.0).is_err());
+}
+
+#[test]
+fn sub_seek_without_line() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+    play(&mut ctx, VIDEO);
+    let mut nav = Navigator::new(&mut ctx).unwrap();
+    let start = std::time::Instant::now();
+    assert_eq!(nav.sub_seek(1), Ok(false));
+
+    let script = AssBuilder::new()
+        .text("only line")
+        .build_script(64, 48, 0.0, 2.0);
+    ctx.sub_add_ass(&script, "rust").unwrap();
+    assert_eq!(nav.sub_seek(1), Ok(false));
+    assert!(start.elapsed() < TIMEOUT / 2);
+}
+
+#[test]
+fn sub_seek_to_line() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+    play(&mut ctx, VIDEO);
+    ctx.add_subtitle_text(
+        "1\n00:00:00,500 --> 00:00:00,800\nfirst\n\n2\n00:00:01,200 --> 00:00:01,500\nsecond\n",
+        SubtitleFlags::Select,
+        None,
+        None,
+    )
+    .unwrap();
+    ctx.set_sub_delay(0.1).unwrap();
+    let mut nav = Navigator::new(&mut ctx).unwrap();
+
+    assert_eq!(nav.sub_seek(1), Ok(true));
+    assert!((time_pos(&mut ctx) - 0.6).abs() < 0.05);
+    assert_eq!(nav.sub_seek(1), Ok(true));
+    assert!((time_pos(&mut ctx) - 1.3).abs() < 0.05);
+    assert_eq!(nav.sub_seek(-1), Ok(true));
+    assert!((time_pos(&mut ctx) - 0.6).abs() < 0.05);
+    // The delay is left alone.
+    assert_eq!(ctx.get_property("sub-delay".into()), Ok(Node::Float64(0.1)));
+}
+
+#[test]
+fn ab_loop() {
+    let mut ctx = MpvHandle::for_tests();
+    assert_eq!(ctx.ab_loop(), Ok((None, None)));
+    ctx.set_ab_loop(Some(0.5), Some(1.5)).unwrap();
+    assert_eq!(ctx.ab_loop(), Ok((Some(0.5), Some(1.5))));
+    ctx.set_ab_loop_count(Some(2)).unwrap();
+    assert_eq!(ctx.get_property("ab-loop-count".into()), Ok(Node::Int64(2)));
+    ctx.set_ab_loop_count(None).unwrap();
+    ctx.set_ab_loop(None, None).unwrap();
+    assert_eq!(ctx.ab_loop(), Ok((None, None)));
+}
+
+#[test]
+fn outlived_by_navigator() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut nav = Navigator::new(&mut ctx).unwrap();
+    // Doesn't wait for the navigator's client.
+    ctx.terminate();
+    match nav.seek_exact(1.0) {
+        Err(WaitError::Mpv(err)) => assert_eq!(err.code, MpvError::Uninitialized),
+        res => panic!("seek after terminate: {res:?}"),
+    }
+}
@@ -0,0 +1,72 @@
+mod common;
+
+#[cfg(feature = "libmpv")]
+use common::*;
+use libmpv_rs::safe::*;
+
+fn handle_all(state: &mut PlayerState, player: &mut impl Player) -> Vec<String> {
+    let mut changed = vec![];
+    while let Some(event) = player.wait_event(0.0) {
+        changed.extend(state.handle_event(&event).map(str::to_owned));
+    }
+    changed
+}
+
+#[test]
+fn tracks_changes() {
+    let mut player = MockPlayer::new();
+    player.set("volume", Node::Float64(100.0));
+    player.set("pause", Node::Flag(false));
+
+    let mut state = PlayerState::new(7);
+    state.observe_as::<f64>(&mut player, "volume").unwrap();
+    state.observe_as::<bool>(&mut player, "pause").unwrap();
+    state.observe_as::<String>(&mut player, "path").unwrap();
+    assert_eq!(state.get::<f64>("volume"), None);
+
+    assert_eq!(handle_all(&mut state, &mut player), ["volume", "pause"]);
+    assert_eq!(state.get::<f64>("volume"), Some(100.0));
+    assert_eq!(state.get::<bool>("pause"), Some(false));
+    assert_eq!(state.get::<String>("path"), None);
+    assert_eq!(state.get::<i64>("pause"), None);
+    assert_eq!(state.take_changes(), ["pause", "volume"]);
+    assert!(state.take_changes().is_empty());
+
+    player.set("volume", Node::Float64(50.0));
+    player.set("path", Node::from("a.mkv"));
+    // Unchanged values and other properties are ignored.
+    player.set("pause", Node::Flag(false));
+    player.set("speed", Node::Float64(2.0));
+    assert_eq!(handle_all(&mut state, &mut player), ["volume", "path"]);
+    assert!(state.is_changed("path"));
+    assert!(!state.is_changed("pause"));
+    assert_eq!(state.snapshot().len(), 3);
+    assert_eq!(state.take_changes(), ["path", "volume"]);
+
+    player.unset("path");
+    assert_eq!(handle_all(&mut state, &mut player), ["path"]);
+    assert_eq!(state.node("path"), None);
+
+    state.clear(&mut player).unwrap();
+    player.set("volume", Node::Float64(0.0));
+    assert!(handle_all(&mut state, &mut player).is_empty());
+    assert_eq!(state.properties().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "libmpv")]
+fn follows_player() {
+    let mut ctx = MpvHandle::for_tests();
+    let mut state = PlayerState::new(1);
+    state.observe_as::<f64>(&mut ctx, "volume").unwrap();
+    state.observe_as::<bool>(&mut ctx, "pause").unwrap();
+
+    ctx.set_property("volume".into(), Node::Float64(30.0))
+        .unwrap();
+    wait_for(&mut ctx, |e| {
+        state.handle_event(e);
+        state.get::<f64>("volume") == Some(30.0)
+    });
+    assert_eq!(state.get::<bool>("pause"), Some(false));
+    assert!(state.is_changed("volume"));
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+const SRT: &str =
+    "1\n00:00:00,000 --> 00:00:01,000\nHello\n\n2\n00:00:01,000 --> 00:00:02,000\nWorld\n";
+
+fn sub_tracks(ctx: &mut MpvHandle) -> Vec<Node> {
+    let Ok(Node::Array(tracks)) = ctx.get_property("track-list".into()) else {
+        panic!("no track list");
+    };
+    tracks
+        .into_iter()
+        .filter(|t| matches!(t, Node::Map(m) if m.get("type") == Some(&Node::from("sub"))))
+        .collect()
+}
+
+#[test]
+fn add_and_remove() {
+    let mut ctx = MpvHandle::for_tests();
+    ctx.set_property("pause".into(), Node::Flag(true)).unwrap();
+    play(&mut ctx, VIDEO);
+
+    ctx.add_subtitle_text(SRT, SubtitleFlags::Select, Some("Greeting"), Some("en"))
+        .unwrap();
+    let tracks = sub_tracks(&mut ctx);
+    assert_eq!(tracks.len(), 1);
+    let Node::Map(track) = &tracks[0] else {
+        unreachable!()
+    };
+    assert_eq!(track.get("title"), Some(&Node::from("Greeting")));
+    assert_eq!(track.get("lang"), Some(&Node::from("en")));
+    assert_eq!(track.get("selected"), Some(&Node::Flag(true)));
+
+    ctx.add_subtitle_text(SRT, SubtitleFlags::Auto, None, None)
+        .unwrap();
+    assert_eq!(sub_tracks(&mut ctx).len(), 2);
+    ctx.set_secondary_sid(Some(2)).unwrap();
+    ctx.set_secondary_sid(None).unwrap();
+    ctx.set_sub_delay(0.5).unwrap();
+    assert_eq!(ctx.get_property("sub-delay".into()), Ok(Node::Float64(0.5)));
+
+    ctx.reload_subtitle(None).unwrap();
+    ctx.remove_subtitle(Some(2)).unwrap();
+    ctx.remove_subtitle(None).unwrap();
+    assert!(sub_tracks(&mut ctx).is_empty());
+    assert!(ctx.remove_subtitle(Some(5)).is_err());
+}
+
+#[test]
+fn active_line() {
+    let mut ctx = MpvHandle::for_tests();
+    play(&mut ctx, VIDEO);
+    ctx.add_subtitle_text(SRT, SubtitleFlags::Select, None, None)
+        .unwrap();
+
+    let mut watcher = SubtitleWatcher::start(&mut ctx, 1).unwrap();
+    let mut lines = vec![];
+    let deadline = std::time::Instant::now() + TIMEOUT;
+    while std::time::Instant::now() < deadline {
+        let Some(event) = ctx.wait_event(0.1) else {
+            continue;
+        };
+        if matches!(event, Event::EndFile { .. }) {
+            break;
+        }
+        if watcher.handle_event(&mut ctx, &event) {
+            lines.push(watcher.current().map(|line| line.text.clone()));
+        }
+    }
+    let shown: Vec<_> = lines.iter().flatten().map(String::as_str).collect();
+    assert_eq!(shown, ["Hello", "World"]);
+    watcher.stop(&mut ctx).unwrap();
+}
+
+#[test]
+fn no_subtitles() {
+    let mut ctx = MpvHandle::for_tests();
+    assert_eq!(ctx.subtitle_text(), Ok(None));
+    play(&mut ctx, VIDEO);
+    assert_eq!(ctx.subtitle_text(), Ok(None));
+}
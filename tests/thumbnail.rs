@@ -0,0 +1,68 @@
+#![cfg(feature = "libmpv")]
+
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use libmpv_rs::safe::*;
+
+fn thumbnailer() -> Thumbnailer {
+    let mut thumbnailer = Thumbnailer::new().unwrap();
+    thumbnailer.open(Path::new(VIDEO)).unwrap();
+    thumbnailer
+}
+
+#[test]
+fn frames_at_times() {
+    let mut thumbnailer = thumbnailer();
+    assert!((thumbnailer.duration() - 2.0).abs() < 0.2);
+    let thumbnails = thumbnailer.frames(&[0.0, 0.5, 1.5]).unwrap();
+    assert_eq!(thumbnails.len(), 3);
+    for thumbnail in &thumbnails {
+        assert_eq!((thumbnail.frame.width, thumbnail.frame.height), (64, 48));
+    }
+    // testsrc draws a moving pattern, so frames at different times differ.
+    assert_ne!(thumbnails[0].frame.data, thumbnails[2].frame.data);
+}
+
+#[test]
+fn scaled_frames() {
+    let mut thumbnailer = thumbnailer();
+    thumbnailer.set_width(Some(32)).unwrap();
+    let thumbnail = thumbnailer.frame_at(1.0).unwrap();
+    assert_eq!((thumbnail.frame.width, thumbnail.frame.height), (32, 24));
+}
+
+#[test]
+fn sprite_sheet_and_webvtt() {
+    let mut thumbnailer = thumbnailer();
+    let sheet = thumbnailer.sprite_sheet(&[0.0, 0.5, 1.0], 2).unwrap();
+    assert_eq!((sheet.image.width, sheet.image.height), (128, 96));
+    assert_eq!(sheet.tile_rect(2), (0, 48, 64, 48));
+
+    let vtt = sheet.webvtt("sheet.png");
+    assert!(vtt.starts_with("WEBVTT\n"));
+    assert!(vtt.contains("00:00:00.000 --> 00:00:00.500\nsheet.png#xywh=0,0,64,48\n"));
+    assert!(vtt.contains("00:00:00.500 --> 00:00:01.000\nsheet.png#xywh=64,0,64,48\n"));
+}
+
+#[test]
+fn open_missing_file() {
+    let mut thumbnailer = Thumbnailer::new().unwrap();
+    let err = thumbnailer
+        .open(Path::new("/nonexistent/file.mkv"))
+        .unwrap_err();
+    assert_eq!(err.operation(), &Operation::Command("loadfile".into()));
+}
+
+#[test]
+fn open_times_out() {
+    let mut thumbnailer = Thumbnailer::new().unwrap();
+    thumbnailer.timeout = 0.0;
+    let err = thumbnailer.open(Path::new(VIDEO)).unwrap_err();
+    assert_eq!(
+        err,
+        WaitError::Timeout(Operation::Command("loadfile".into()))
+    );
+}